@@ -0,0 +1,83 @@
+// Hover cards for the links to other entries ([[entry:<id>]])
+window.addEventListener("DOMContentLoaded", waitForEntryLinkElements);
+
+function waitForEntryLinkElements() {
+  if (window.content) {
+    initializeEntryLinks();
+  } else {
+    setTimeout(waitForEntryLinkElements, 100);
+  }
+}
+
+let entryCard = null;
+let entryCardTimer = null;
+
+function initializeEntryLinks() {
+  entryCard = document.createElement("div");
+  entryCard.classList.add("entry-card");
+  document.body.appendChild(entryCard);
+
+  window.content.addEventListener("mouseover", (event) => {
+    const link = event.target.closest(".entry-link");
+    if (!link) return;
+    clearTimeout(entryCardTimer);
+    entryCardTimer = setTimeout(() => showEntryCard(link), 250);
+  });
+  window.content.addEventListener("mouseout", (event) => {
+    const link = event.target.closest(".entry-link");
+    if (!link || link.contains(event.relatedTarget)) return;
+    clearTimeout(entryCardTimer);
+    hideEntryCard();
+  });
+  window.content.addEventListener("scroll", hideEntryCard);
+}
+
+// Built from the rendered entry, so only the loaded entries have a card
+function showEntryCard(link) {
+  const id = link.getAttribute("data-entry");
+  const entry = document.getElementById(`entry-${id}`);
+
+  entryCard.innerHTML = "";
+  if (entry) {
+    const header = document.createElement("div");
+    header.classList.add("entry-card-header");
+    const time = entry.querySelector(".timestamp");
+    header.textContent = `${entry.dataset.date} ${time ? time.textContent.trim() : ""}`;
+    if (entry.dataset.author) {
+      const author = document.createElement("span");
+      author.classList.add("entry-card-author");
+      author.textContent = entry.dataset.author;
+      header.appendChild(author);
+    }
+
+    const body = document.createElement("div");
+    body.classList.add("entry-card-body");
+    const text = entry.querySelector(".log-text");
+    const lines = (text ? text.innerText : "")
+      .split("\n")
+      .map(line => line.trim())
+      .filter(line => line !== "")
+      .slice(0, 4);
+    body.textContent = lines.join("\n");
+
+    entryCard.append(header, body);
+  } else {
+    entryCard.textContent = `Entry ${id} is not loaded`;
+  }
+
+  // Below the link, or above if no room
+  const rect = link.getBoundingClientRect();
+  entryCard.classList.add("open");
+  const cardHeight = entryCard.offsetHeight;
+  const top = rect.bottom + cardHeight + 8 > window.innerHeight
+    ? rect.top - cardHeight - 4
+    : rect.bottom + 4;
+  entryCard.style.left = `${Math.min(rect.left, window.innerWidth - entryCard.offsetWidth - 8)}px`;
+  entryCard.style.top = `${Math.max(4, top)}px`;
+}
+
+function hideEntryCard() {
+  if (entryCard) {
+    entryCard.classList.remove("open");
+  }
+}
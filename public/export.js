@@ -0,0 +1,145 @@
+// Standalone reports (see. src/export.rs)
+// The attachment previews are replaced by embedded copies, so that the file
+// does not depend on the backend nor on the download tokens.
+
+async function exportReport(html, filename, print) {
+  const doc = new DOMParser().parseFromString(html, "text/html");
+  await embedReportAttachments(doc);
+  const output = "<!DOCTYPE html>\n" + doc.documentElement.outerHTML;
+
+  if (print) {
+    // The browser saves it as PDF from the print dialog
+    printReport(output);
+  } else {
+    saveFile(output, filename, "text/html");
+  }
+}
+
+// Printed from a hidden frame of this page: a window opened after the
+// awaits above is no longer from the click, and the popup blockers reject it
+function printReport(output) {
+  // The frame of an earlier report, when its afterprint never came
+  document.getElementById("report-print-frame")?.remove();
+  const frame = document.createElement("iframe");
+  frame.id = "report-print-frame";
+  frame.setAttribute("aria-hidden", "true");
+  frame.style.position = "fixed";
+  frame.style.width = "0";
+  frame.style.height = "0";
+  frame.style.border = "0";
+  document.body.appendChild(frame);
+
+  const frameDocument = frame.contentWindow.document;
+  frameDocument.open();
+  frameDocument.write(output);
+  frameDocument.close();
+  // Let the embedded images be laid out first
+  setTimeout(() => {
+    frame.contentWindow.addEventListener("afterprint", () => frame.remove());
+    frame.contentWindow.focus();
+    frame.contentWindow.print();
+  }, 500);
+}
+
+// Let the browser download the data as a file
+function saveFile(data, filename, mime) {
+  const url = URL.createObjectURL(new Blob([data], { type: mime }));
+  const link = document.createElement("a");
+  link.href = url;
+  link.download = filename;
+  link.click();
+  setTimeout(() => URL.revokeObjectURL(url), 1000);
+}
+
+async function embedReportAttachments(doc) {
+  const elements = doc.querySelectorAll(
+    "[class*='-attachment'][data-id], .attachment-download");
+  for (const element of elements) {
+    try {
+      await embedReportAttachment(doc, element);
+    } catch (error) {
+      console.error("Error embedding attachment:", error);
+      element.replaceWith(reportFileLabel(doc, element));
+    }
+  }
+}
+
+async function embedReportAttachment(doc, element) {
+  const url = element.getAttribute("data-url");
+  const kind = Array.from(element.classList).find(name => name.endsWith("-attachment"));
+  const fetchText = async () => {
+    const response = await fetch(url, { cache: "force-cache" });
+    if (!response.ok) throw new Error(response.statusText);
+    return response.text();
+  };
+
+  switch (kind) {
+    case "image-attachment": {
+      const response = await fetch(url, { cache: "force-cache" });
+      if (!response.ok) throw new Error(response.statusText);
+      reportImage(doc, element, await blobToBase64(await response.blob()));
+      break;
+    }
+    case "svg-attachment": {
+      const sanitized = sanitizeSvg(await fetchText());
+      if (sanitized === null) throw new Error("Invalid SVG");
+      reportImage(doc, element, await blobToBase64(new Blob([sanitized], { type: "image/svg+xml" })));
+      break;
+    }
+    case "plot-attachment": {
+      // Drawn by the same code as in the timeline, then kept as an image
+      const canvas = document.createElement("canvas");
+      canvas.width = 1200;
+      canvas.height = 600;
+      const message = window.draw_plot(
+        canvas, await fetchText(), element.getAttribute("data-delimiter"),
+        element.getAttribute("data-options") || "{}");
+      if (message) throw new Error(message);
+      reportImage(doc, element, canvas.toDataURL("image/png"));
+      break;
+    }
+    case "table-attachment": {
+      const delimiter = element.getAttribute("data-delimiter") === "tab" ? "\t" : ",";
+      const rows = parseDelimited(await fetchText(), delimiter);
+      const table = doc.createElement("table");
+      rows.forEach((cells, i) => {
+        const tr = table.insertRow();
+        cells.forEach(cell => {
+          const td = doc.createElement(i === 0 ? "th" : "td");
+          td.textContent = cell;
+          tr.appendChild(td);
+        });
+      });
+      element.replaceWith(table);
+      break;
+    }
+    case "text-attachment":
+    case "code-attachment":
+    case "tree-attachment": {
+      const pre = doc.createElement("pre");
+      pre.textContent = await fetchText();
+      element.replaceWith(pre);
+      break;
+    }
+    default:
+      // PDF, audio, video and the other files are only named
+      element.replaceWith(reportFileLabel(doc, element));
+  }
+}
+
+function reportImage(doc, element, src) {
+  const img = doc.createElement("img");
+  img.src = src;
+  img.alt = element.getAttribute("name") || "";
+  if (element.style.width) img.style.width = element.style.width;
+  element.replaceWith(img);
+}
+
+function reportFileLabel(doc, element) {
+  const label = doc.createElement("span");
+  label.classList.add("report-file");
+  label.textContent = element.getAttribute("name")
+    || element.getAttribute("download")
+    || element.textContent.trim();
+  return label;
+}
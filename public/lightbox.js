@@ -0,0 +1,247 @@
+// Full-screen viewer for the inline images
+window.addEventListener("DOMContentLoaded", waitForLightboxElements);
+
+function waitForLightboxElements() {
+  if (window.content) {
+    initializeLightbox();
+  } else {
+    setTimeout(waitForLightboxElements, 100);
+  }
+}
+
+const lightbox = {
+  element: null,
+  image: null,
+  caption: null,
+  images: [], // { src, url, name }
+  index: 0,
+  scope: "entry", // "entry" or "day"
+  origin: null, // The clicked attachment element
+  scale: 1,
+  x: 0,
+  y: 0,
+};
+
+function initializeLightbox() {
+  lightbox.element = document.createElement("div");
+  lightbox.element.classList.add("lightbox");
+  lightbox.element.innerHTML = `
+    <div class="lightbox-toolbar">
+      <span class="lightbox-caption"></span>
+      <button data-action="scope"></button>
+      <button data-action="zoom-out"><i class="material-symbols-outlined" aria-hidden="true">zoom_out</i></button>
+      <button data-action="reset"><i class="material-symbols-outlined" aria-hidden="true">fit_screen</i></button>
+      <button data-action="zoom-in"><i class="material-symbols-outlined" aria-hidden="true">zoom_in</i></button>
+      <button data-action="open"><i class="material-symbols-outlined" aria-hidden="true">open_in_new</i></button>
+      <button data-action="download"><i class="material-symbols-outlined" aria-hidden="true">download</i></button>
+      <button data-action="close"><i class="material-symbols-outlined" aria-hidden="true">close</i></button>
+    </div>
+    <button class="lightbox-nav lightbox-prev" data-action="prev"><i class="material-symbols-outlined" aria-hidden="true">chevron_left</i></button>
+    <div class="lightbox-stage"><img class="lightbox-image" draggable="false"/></div>
+    <button class="lightbox-nav lightbox-next" data-action="next"><i class="material-symbols-outlined" aria-hidden="true">chevron_right</i></button>
+  `;
+  document.body.appendChild(lightbox.element);
+  lightbox.image = lightbox.element.querySelector(".lightbox-image");
+  lightbox.caption = lightbox.element.querySelector(".lightbox-caption");
+
+  // Open by clicking an inline image
+  window.content.addEventListener("click", (event) => {
+    const img = event.target.closest(".image-attachment img");
+    if (img) {
+      openLightbox(img.closest(".image-attachment"));
+    }
+  });
+
+  // Toolbar and navigation
+  lightbox.element.addEventListener("click", (event) => {
+    const button = event.target.closest("[data-action]");
+    if (button) {
+      lightboxAction(button.dataset.action);
+    } else if (event.target.classList.contains("lightbox-stage")) {
+      closeLightbox();
+    }
+  });
+
+  document.addEventListener("keydown", (event) => {
+    if (!lightbox.element.classList.contains("open")) return;
+    const actions = {
+      Escape: "close",
+      ArrowLeft: "prev",
+      ArrowRight: "next",
+      "+": "zoom-in",
+      "=": "zoom-in",
+      "-": "zoom-out",
+      "0": "reset",
+      o: "open",
+      d: "download",
+      s: "scope",
+    };
+    if (actions[event.key]) {
+      event.preventDefault();
+      // Also from the other listeners of the document, like the key bindings
+      event.stopImmediatePropagation();
+      lightboxAction(actions[event.key]);
+    }
+  }, true);
+
+  // Zoom by the wheel around the cursor
+  lightbox.element.addEventListener("wheel", (event) => {
+    event.preventDefault();
+    const factor = event.deltaY < 0 ? 1.2 : 1 / 1.2;
+    zoomLightbox(factor, event.clientX, event.clientY);
+  }, { passive: false });
+
+  // Pan by dragging
+  let dragging = null;
+  lightbox.image.addEventListener("mousedown", (event) => {
+    event.preventDefault();
+    dragging = { x: event.clientX - lightbox.x, y: event.clientY - lightbox.y };
+    lightbox.image.classList.add("dragging");
+  });
+  document.addEventListener("mousemove", (event) => {
+    if (!dragging) return;
+    lightbox.x = event.clientX - dragging.x;
+    lightbox.y = event.clientY - dragging.y;
+    applyLightboxTransform();
+  });
+  document.addEventListener("mouseup", () => {
+    dragging = null;
+    lightbox.image.classList.remove("dragging");
+  });
+}
+
+// Collect the loaded images of the same entry or of the same day
+function collectLightboxImages(origin, scope) {
+  let root = origin.closest(".entry-item");
+  let elements;
+  if (scope === "day" && root) {
+    const date = root.dataset.date;
+    elements = Array.from(window.content.querySelectorAll(`.entry-item[data-date='${date}'] .image-attachment`));
+  } else {
+    elements = Array.from((root || window.content).querySelectorAll(".image-attachment"));
+  }
+  return elements
+    .filter(element => element.querySelector("img"))
+    .map(element => ({
+      element,
+      src: element.querySelector("img").src,
+      url: element.getAttribute("data-url"),
+      name: element.getAttribute("name") || "image",
+    }));
+}
+
+function openLightbox(origin) {
+  lightbox.origin = origin;
+  lightbox.images = collectLightboxImages(origin, lightbox.scope);
+  lightbox.index = Math.max(0, lightbox.images.findIndex(image => image.element === origin));
+  lightbox.element.classList.add("open");
+  showLightboxImage();
+}
+
+function closeLightbox() {
+  lightbox.element.classList.remove("open");
+  lightbox.image.removeAttribute("src");
+  lightbox.images = [];
+}
+
+function showLightboxImage() {
+  const current = lightbox.images[lightbox.index];
+  if (!current) return;
+  lightbox.image.src = current.src;
+  lightbox.image.alt = current.name;
+  lightbox.caption.textContent = `${current.name} (${lightbox.index + 1} / ${lightbox.images.length})`;
+  lightbox.element.querySelector("[data-action='scope']").textContent =
+    uiText(lightbox.scope);
+  updateLightboxTitles();
+  lightbox.element.classList.toggle("single", lightbox.images.length < 2);
+  resetLightboxTransform();
+}
+
+// Titles of the buttons by action: the string, and the key in parentheses
+const lightboxTitles = {
+  scope: ["image-scope", "S"],
+  "zoom-out": ["zoom-out", "-"],
+  reset: ["fit", "0"],
+  "zoom-in": ["zoom-in", "+"],
+  open: ["open-original", "O"],
+  download: ["download", "D"],
+  close: ["close", "Esc"],
+  prev: ["previous", "←"],
+  next: ["next", "→"],
+};
+
+// In the language of the app, which may have changed since the last time
+function updateLightboxTitles() {
+  for (const [action, [name, key]] of Object.entries(lightboxTitles)) {
+    lightbox.element.querySelector(`[data-action='${action}']`).title = `${uiText(name)} (${key})`;
+  }
+}
+
+function lightboxAction(action) {
+  const current = lightbox.images[lightbox.index];
+  switch (action) {
+    case "close":
+      closeLightbox();
+      break;
+    case "prev":
+    case "next":
+      if (lightbox.images.length > 0) {
+        const step = action === "next" ? 1 : -1;
+        lightbox.index = (lightbox.index + step + lightbox.images.length) % lightbox.images.length;
+        showLightboxImage();
+      }
+      break;
+    case "zoom-in":
+      zoomLightbox(1.2);
+      break;
+    case "zoom-out":
+      zoomLightbox(1 / 1.2);
+      break;
+    case "reset":
+      resetLightboxTransform();
+      break;
+    case "open":
+      if (current) window.open(current.url, "_blank");
+      break;
+    case "download":
+      if (current) {
+        const a = document.createElement("a");
+        a.href = current.url;
+        a.download = current.name;
+        a.click();
+      }
+      break;
+    case "scope":
+      lightbox.scope = lightbox.scope === "entry" ? "day" : "entry";
+      if (current) {
+        lightbox.images = collectLightboxImages(current.element, lightbox.scope);
+        lightbox.index = Math.max(0, lightbox.images.findIndex(image => image.element === current.element));
+      }
+      showLightboxImage();
+      break;
+  }
+}
+
+// Zoom keeping the point (cx, cy) fixed; the centre of the screen by default
+function zoomLightbox(factor, cx = window.innerWidth / 2, cy = window.innerHeight / 2) {
+  const scale = Math.min(20, Math.max(0.2, lightbox.scale * factor));
+  const applied = scale / lightbox.scale;
+  const ox = cx - window.innerWidth / 2;
+  const oy = cy - window.innerHeight / 2;
+  lightbox.x = ox - (ox - lightbox.x) * applied;
+  lightbox.y = oy - (oy - lightbox.y) * applied;
+  lightbox.scale = scale;
+  applyLightboxTransform();
+}
+
+function resetLightboxTransform() {
+  lightbox.scale = 1;
+  lightbox.x = 0;
+  lightbox.y = 0;
+  applyLightboxTransform();
+}
+
+function applyLightboxTransform() {
+  lightbox.image.style.transform =
+    `translate(${lightbox.x}px, ${lightbox.y}px) scale(${lightbox.scale})`;
+}
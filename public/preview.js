@@ -17,28 +17,42 @@ function observeContentChanges() {
   observer.observe(window.content, config);
 }
 
+// Text-based previews: the class name and the function rendering the fetched text
+const textPreviewRenderers = {
+  "text-attachment": (id, content) => updateTextAttachment(id, content),
+  "svg-attachment": (id, content) => updateSvgAttachment(id, content),
+  "table-attachment": (id, content) => updateTableAttachment(id, content),
+  "tree-attachment": (id, content) => updateTreeAttachment(id, content),
+  "code-attachment": (id, content) => updateCodeAttachment(id, content),
+  "plot-attachment": (id, content) => updatePlotAttachment(id, content),
+};
+
 function initializeAttachmentPreviews() {
-  const attachmentElements = window.content.querySelectorAll(".image-attachment, .text-attachment, .pdf-attachment");
+  const textSelectors = Object.keys(textPreviewRenderers).map(name => `.${name}`);
+  const attachmentElements = window.content.querySelectorAll(
+    [".image-attachment", ".pdf-attachment"].concat(textSelectors).join(", "));
 
   attachmentElements.forEach(element => {
     if (!element.dataset.initialized) {
       const url = element.getAttribute("data-url");
       const id = element.getAttribute("data-id");
+      const textClass = Object.keys(textPreviewRenderers).find(name => element.classList.contains(name));
 
       // Try to find it from the cache
       if (previewCache.has(id)) {
         const cachedContent = previewCache.get(id);
-        if (element.classList.contains("text-attachment")) {
-          updateTextAttachment(id, cachedContent.content);
+        if (textClass) {
+          textPreviewRenderers[textClass](id, cachedContent.content);
         } else if (element.classList.contains("pdf-attachment")) {
           updatePdfAttachment(id, cachedContent.objectUrl);
         } else if (element.classList.contains("image-attachment")) {
           updateImageAttachment(id, cachedContent.base64, cachedContent.name);
         }
+        markLoaded(id);
         // Fetch it newly
       } else {
-        if (element.classList.contains("text-attachment")) {
-          fetchPreview(url, id, "text");
+        if (textClass) {
+          fetchPreview(url, id, "text", textPreviewRenderers[textClass]);
         } else if (element.classList.contains("pdf-attachment")) {
           fetchPreview(url, id, "pdf");
         } else if (element.classList.contains("image-attachment")) {
@@ -61,7 +75,8 @@ async function fetchPreview(url, id, type, opt = "") {
     let content;
     if (type === "text") {
       content = await response.text();
-      updateTextAttachment(id, content);
+      // `opt` is the renderer for the text
+      opt(id, content);
       previewCache.set(id, { type: "text", content });
     } else if (type === "pdf" || type === "image") {
       content = await response.blob();
@@ -71,16 +86,25 @@ async function fetchPreview(url, id, type, opt = "") {
         previewCache.set(id, { type, objectUrl });
       } else if (type === "image") {
         const base64 = await blobToBase64(content);
-        updateImageAttachment(id, base64);
+        updateImageAttachment(id, base64, opt);
         previewCache.set(id, { type, base64, name: opt });
       }
 
     }
   } catch (error) {
     console.error("Error fetching preview:", error);
+  } finally {
+    markLoaded(id);
   }
 }
 
+// No longer busy for the screen readers (see. render.rs)
+function markLoaded(id) {
+  window.content.querySelectorAll(`[data-id='${id}'][aria-busy]`).forEach(element => {
+    element.removeAttribute("aria-busy");
+  });
+}
+
 function blobToBase64(blob) {
   return new Promise((resolve, reject) => {
     const reader = new FileReader();
@@ -91,27 +115,370 @@ function blobToBase64(blob) {
 }
 
 function updateTextAttachment(id, content) {
-  const element = window.content.querySelector(`.text-attachment[data-id='${id}']`);
-  if (element) {
+  // The same attachment can be referenced more than once
+  window.content.querySelectorAll(`.text-attachment[data-id='${id}']`).forEach(element => {
     element.innerHTML = `<pre>${content}</pre>`;
-  }
+  });
 }
 
 function updatePdfAttachment(id, pdfUrl) {
-  const element = window.content.querySelector(`.pdf-attachment[data-id='${id}']`);
-  if (element) {
-    element.innerHTML = `<iframe src="${pdfUrl}" type="application/pdf"></iframe>`;
-  }
+  window.content.querySelectorAll(`.pdf-attachment[data-id='${id}']`).forEach(element => {
+    const page = element.getAttribute("data-page");
+    const src = page ? `${pdfUrl}#page=${page}` : pdfUrl;
+    element.innerHTML = `<iframe src="${src}" type="application/pdf"></iframe>`;
+  });
 }
 
 function updateImageAttachment(id, base64, name) {
-  const element = window.content.querySelector(`.image-attachment[data-id='${id}']`);
-  if (element) {
+  window.content.querySelectorAll(`.image-attachment[data-id='${id}']`).forEach(element => {
     element.innerHTML = `<img src="${base64}" alt="${name}"/>`;
+  });
+}
+
+
+
+function escapeHtml(text) {
+  return text
+    .replace(/&/g, "&amp;")
+    .replace(/</g, "&lt;")
+    .replace(/>/g, "&gt;")
+    .replace(/"/g, "&quot;")
+    .replace(/'/g, "&#39;");
+}
+
+// Strings in the language of the app, set on the root element (see. Locale::apply)
+function uiText(name) {
+  return document.documentElement.getAttribute(`data-text-${name}`) ?? name;
+}
+
+function previewElements(kind, id) {
+  return window.content.querySelectorAll(`.${kind}-attachment[data-id='${id}']`);
+}
+
+function showPreviewError(element, message) {
+  element.innerHTML = `<span class="preview-error">${escapeHtml(message)}</span>`;
+}
+
+// SVG ---------------------------------------------------------------------------
+
+// Drop everything able to run scripts or to load external resources
+function sanitizeSvg(source) {
+  const doc = new DOMParser().parseFromString(source, "image/svg+xml");
+  const svg = doc.documentElement;
+  if (!svg || svg.nodeName.toLowerCase() !== "svg" || doc.querySelector("parsererror")) {
+    return null;
+  }
+  svg.querySelectorAll("script, foreignObject, iframe, object, embed").forEach(node => node.remove());
+  [svg, ...svg.querySelectorAll("*")].forEach(node => {
+    Array.from(node.attributes).forEach(attr => {
+      const name = attr.name.toLowerCase();
+      const value = attr.value.trim().toLowerCase();
+      if (name.startsWith("on")) {
+        node.removeAttribute(attr.name);
+      } else if ((name === "href" || name === "xlink:href") && !value.startsWith("#") && !value.startsWith("data:image/")) {
+        node.removeAttribute(attr.name);
+      }
+    });
+  });
+  return new XMLSerializer().serializeToString(svg);
+}
+
+function updateSvgAttachment(id, content) {
+  const sanitized = sanitizeSvg(content);
+  previewElements("svg", id).forEach(element => {
+    if (sanitized === null) {
+      showPreviewError(element, uiText("invalid-svg"));
+      return;
+    }
+    // Shown as an image so that nothing inside can touch the page
+    const url = URL.createObjectURL(new Blob([sanitized], { type: "image/svg+xml" }));
+    const name = element.getAttribute("name") || "";
+    element.innerHTML = `<img src="${url}" alt="${escapeHtml(name)}"/>`;
+  });
+}
+
+// CSV / TSV ---------------------------------------------------------------------
+
+function parseDelimited(text, delimiter) {
+  const rows = [];
+  let row = [];
+  let field = "";
+  let quoted = false;
+  for (let i = 0; i < text.length; i++) {
+    const c = text[i];
+    if (quoted) {
+      if (c === '"' && text[i + 1] === '"') {
+        field += '"';
+        i++;
+      } else if (c === '"') {
+        quoted = false;
+      } else {
+        field += c;
+      }
+    } else if (c === '"' && field === "") {
+      quoted = true;
+    } else if (c === delimiter) {
+      row.push(field);
+      field = "";
+    } else if (c === "\n" || c === "\r") {
+      if (c === "\r" && text[i + 1] === "\n") i++;
+      row.push(field);
+      rows.push(row);
+      row = [];
+      field = "";
+    } else {
+      field += c;
+    }
   }
+  if (field !== "" || row.length > 0) {
+    row.push(field);
+    rows.push(row);
+  }
+  return rows.filter(r => !(r.length === 1 && r[0] === ""));
+}
+
+function updateTableAttachment(id, content) {
+  previewElements("table", id).forEach(element => {
+    const delimiter = element.getAttribute("data-delimiter") === "tab" ? "\t" : ",";
+    const rows = parseDelimited(content, delimiter);
+    if (rows.length === 0) {
+      showPreviewError(element, uiText("empty-table"));
+      return;
+    }
+    const [header, ...body] = rows;
+    element.innerHTML = "";
+    const table = document.createElement("table");
+    table.classList.add("preview-table");
+    const thead = table.createTHead().insertRow();
+    header.forEach((name, column) => {
+      const th = document.createElement("th");
+      th.textContent = name;
+      th.title = uiText("sort");
+      th.onclick = () => sortPreviewTable(table, column, th);
+      thead.appendChild(th);
+    });
+    const tbody = table.createTBody();
+    body.forEach(cells => {
+      const tr = tbody.insertRow();
+      cells.forEach(cell => { tr.insertCell().textContent = cell; });
+    });
+    const plotButton = document.createElement("button");
+    plotButton.classList.add("table-plot-button");
+    plotButton.innerHTML = '<i class="material-symbols-outlined" aria-hidden="true">show_chart</i>' + escapeHtml(uiText("plot"));
+    plotButton.onclick = () => {
+      element.classList.replace("table-attachment", "plot-attachment");
+      updatePlotAttachment(id, content);
+    };
+    element.append(plotButton, table);
+  });
 }
 
+function sortPreviewTable(table, column, th) {
+  const ascending = th.dataset.sort !== "asc";
+  table.querySelectorAll("th").forEach(other => { delete other.dataset.sort; });
+  th.dataset.sort = ascending ? "asc" : "desc";
 
+  const tbody = table.tBodies[0];
+  const rows = Array.from(tbody.rows);
+  const value = (row) => row.cells[column] ? row.cells[column].textContent : "";
+  rows.sort((a, b) => {
+    const x = value(a);
+    const y = value(b);
+    const nx = parseFloat(x);
+    const ny = parseFloat(y);
+    const order = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+    return ascending ? order : -order;
+  });
+  rows.forEach(row => tbody.appendChild(row));
+}
+
+// JSON / YAML / XML -------------------------------------------------------------
+
+function buildValueTree(value, key) {
+  const label = key === undefined ? "" : `<span class="tree-key">${escapeHtml(String(key))}</span>: `;
+  if (value !== null && typeof value === "object") {
+    const entries = Array.isArray(value) ? value.map((v, i) => [i, v]) : Object.entries(value);
+    const summary = Array.isArray(value) ? `[${entries.length}]` : `{${entries.length}}`;
+    const details = document.createElement("details");
+    details.open = true;
+    details.innerHTML = `<summary>${label}<span class="tree-summary">${summary}</span></summary>`;
+    entries.forEach(([k, v]) => details.appendChild(buildValueTree(v, k)));
+    return details;
+  }
+  const leaf = document.createElement("div");
+  leaf.classList.add("tree-leaf");
+  const type = value === null ? "null" : typeof value;
+  const text = type === "string" ? JSON.stringify(value) : String(value);
+  leaf.innerHTML = `${label}<span class="tree-${type}">${escapeHtml(text)}</span>`;
+  return leaf;
+}
+
+function buildXmlTree(node) {
+  if (node.nodeType === Node.TEXT_NODE) {
+    const leaf = document.createElement("div");
+    leaf.classList.add("tree-leaf");
+    leaf.innerHTML = `<span class="tree-string">${escapeHtml(node.textContent.trim())}</span>`;
+    return leaf;
+  }
+  const attributes = Array.from(node.attributes)
+    .map(attr => ` <span class="tree-key">${escapeHtml(attr.name)}</span>=<span class="tree-string">"${escapeHtml(attr.value)}"</span>`)
+    .join("");
+  const children = Array.from(node.childNodes)
+    .filter(child => child.nodeType === Node.ELEMENT_NODE || (child.nodeType === Node.TEXT_NODE && child.textContent.trim() !== ""));
+  const tag = `&lt;${escapeHtml(node.nodeName)}${attributes}&gt;`;
+  if (children.length === 0) {
+    const leaf = document.createElement("div");
+    leaf.classList.add("tree-leaf");
+    leaf.innerHTML = tag;
+    return leaf;
+  }
+  const details = document.createElement("details");
+  details.open = true;
+  details.innerHTML = `<summary>${tag}</summary>`;
+  children.forEach(child => details.appendChild(buildXmlTree(child)));
+  return details;
+}
+
+function updateTreeAttachment(id, content) {
+  previewElements("tree", id).forEach(element => {
+    const format = element.getAttribute("data-format");
+    let tree;
+    try {
+      if (format === "json") {
+        tree = buildValueTree(JSON.parse(content));
+      } else if (format === "yaml") {
+        tree = buildValueTree(jsyaml.load(content));
+      } else {
+        const doc = new DOMParser().parseFromString(content, "application/xml");
+        if (doc.querySelector("parsererror")) throw new Error("Invalid XML");
+        tree = buildXmlTree(doc.documentElement);
+      }
+    } catch (error) {
+      showPreviewError(element, `${uiText("cannot-parse")} (${format}): ${error.message}`);
+      return;
+    }
+    element.innerHTML = "";
+    element.appendChild(tree);
+  });
+}
+
+// Source code -------------------------------------------------------------------
+
+function updateCodeAttachment(id, content) {
+  previewElements("code", id).forEach(element => {
+    const language = element.getAttribute("data-language");
+    let highlighted = escapeHtml(content);
+    if (window.hljs && hljs.getLanguage(language)) {
+      highlighted = hljs.highlight(content, { language }).value;
+    }
+    element.innerHTML = `<pre><code class="hljs language-${language}">${highlighted}</code></pre>`;
+  });
+}
+
+// Charts ------------------------------------------------------------------------
+
+// The chart itself is drawn by `draw_plot` on the Rust side
+function updatePlotAttachment(id, content) {
+  previewElements("plot", id).forEach(element => {
+    if (element.dataset.plotted) return;
+    element.dataset.plotted = true;
+
+    const delimiter = element.getAttribute("data-delimiter");
+    const options = JSON.parse(element.getAttribute("data-options") || "{}");
+    const columns = Array.from(window.plot_columns(content, delimiter));
+    if (columns.length === 0) {
+      showPreviewError(element, uiText("no-numeric-columns"));
+      return;
+    }
+    // Effective defaults, so that the controls show what is drawn
+    if (options.x === undefined || options.x === null) {
+      options.x = columns.length > 1 ? columns[0] : "";
+    }
+    if (!options.y || options.y.length === 0) {
+      options.y = columns.filter(column => column !== options.x);
+    }
+    options.kind = options.kind || "line";
+
+    element.innerHTML = "";
+    const controls = document.createElement("div");
+    controls.classList.add("plot-controls");
+    const canvas = document.createElement("canvas");
+    canvas.classList.add("plot-canvas");
+    const error = document.createElement("div");
+    error.classList.add("preview-error");
+    element.append(controls, canvas, error);
+
+    const redraw = () => {
+      const ratio = window.devicePixelRatio || 1;
+      canvas.width = canvas.clientWidth * ratio;
+      canvas.height = canvas.clientHeight * ratio;
+      const message = window.draw_plot(canvas, content, delimiter, JSON.stringify(options));
+      error.textContent = message || "";
+    };
+    // Drawn again in the colors of another theme
+    canvas.redraw = redraw;
+
+    // Chart type
+    const kind = document.createElement("select");
+    ["line", "scatter", "histogram"].forEach(value => kind.add(new Option(value, value, false, value === options.kind)));
+    kind.onchange = () => { options.kind = kind.value; xSelect.disabled = kind.value === "histogram"; redraw(); };
+
+    // X column
+    const xSelect = document.createElement("select");
+    xSelect.add(new Option("(row)", "", false, options.x === ""));
+    columns.forEach(column => xSelect.add(new Option(column, column, false, column === options.x)));
+    xSelect.disabled = options.kind === "histogram";
+    xSelect.onchange = () => { options.x = xSelect.value; redraw(); };
+
+    // Y columns
+    const ySpan = document.createElement("span");
+    ySpan.classList.add("plot-columns");
+    columns.forEach(column => {
+      const label = document.createElement("label");
+      const check = document.createElement("input");
+      check.type = "checkbox";
+      check.checked = options.y.includes(column);
+      check.onchange = () => {
+        options.y = columns.filter((c, i) => ySpan.querySelectorAll("input")[i].checked);
+        redraw();
+      };
+      label.append(check, column);
+      ySpan.appendChild(label);
+    });
+
+    // Log axes
+    const logCheck = (name) => {
+      const label = document.createElement("label");
+      const check = document.createElement("input");
+      check.type = "checkbox";
+      check.checked = !!options[name];
+      check.onchange = () => { options[name] = check.checked; redraw(); };
+      label.append(check, name);
+      return label;
+    };
+
+    // PNG export
+    const exportButton = document.createElement("button");
+    exportButton.innerHTML = '<i class="material-symbols-outlined" aria-hidden="true">download</i>PNG';
+    exportButton.onclick = () => {
+      const a = document.createElement("a");
+      a.href = canvas.toDataURL("image/png");
+      a.download = (element.getAttribute("name") || "plot").replace(/\.[^.]*$/, "") + ".png";
+      a.click();
+    };
+
+    controls.append(kind, " x:", xSelect, " y:", ySpan, logCheck("logx"), logCheck("logy"), exportButton);
+    redraw();
+  });
+}
+
+// The charts follow the theme, set on the root element or by the system
+function redrawPlots() {
+  document.querySelectorAll(".plot-canvas").forEach(canvas => canvas.redraw && canvas.redraw());
+}
+new MutationObserver(redrawPlots)
+  .observe(document.documentElement, { attributes: true, attributeFilter: ["data-theme"] });
+window.matchMedia("(prefers-color-scheme: dark)").addEventListener("change", redrawPlots);
 
 // Cache repositories
 const previewCache = new Map();
@@ -30,8 +30,8 @@ function resizePreviewItems() {
 }
 
 // Resize all the items in the window depending on the divider's height
+// The timeline takes the rest of the height by the flex layout
 function resizeItems(newHeight) {
-    window.content.style.height = `${window.innerHeight - newHeight}px`;
     window.footer.style.height = `${newHeight}px`;
     window.textarea.style.height = `${newHeight - 20}px`; // paddingを考慮
 
@@ -46,6 +46,10 @@ function initializeMarkdownEditor() {
         spellChecker: false
     });
 
+    // Named as the textarea it replaces, for the screen readers
+    window.easyMDE.codemirror.getInputField()
+        .setAttribute("aria-label", window.textarea.getAttribute("aria-label"));
+
     // Ctrl+Enter short cut
     window.easyMDE.codemirror.on("keydown", function (instance, event) {
         if (event.ctrlKey && event.key === "Enter") {
@@ -54,13 +58,38 @@ function initializeMarkdownEditor() {
         }
     });
 
+    // Paste & drop attachments directly into the editor
+    window.easyMDE.codemirror.on("paste", function (instance, event) {
+        const files = filesFromTransfer(event.clipboardData, true);
+        if (files.length > 0) {
+            event.preventDefault();
+            stageFiles(files, true);
+        }
+    });
+    window.easyMDE.codemirror.on("drop", function (instance, event) {
+        const files = filesFromTransfer(event.dataTransfer);
+        if (files.length > 0) {
+            event.preventDefault();
+            // Move the cursor to the dropped place before inserting the references
+            const pos = instance.coordsChar({ left: event.clientX, top: event.clientY });
+            instance.setCursor(pos);
+            stageFiles(files, true);
+        }
+    });
+    // Dropping on the timeline attaches as well
+    initialiseTimelineDrop();
+
     // Custom the submit button
     const addEntryButton = document.querySelector(".fa-paper-plane");
     if (addEntryButton) {
         const addEntryText = document.createElement("span");
-        addEntryText.textContent = "Submit (Ctrl+Enter)";
+        const updateText = () => addEntryText.textContent = `${uiText("submit")} (Ctrl+Enter)`;
+        updateText();
         addEntryText.style.marginLeft = "5px";
         addEntryButton.parentNode.appendChild(addEntryText);
+        // Follows the language chosen in the app
+        new MutationObserver(updateText)
+            .observe(document.documentElement, { attributes: true, attributeFilter: ["lang"] });
     }
 
     // Editor style
@@ -86,6 +115,7 @@ async function showFilePreview(file, fileNumber) {
 
     closeButton.onclick = () => {
         fileList = fileList.filter(f => f !== file);
+        renumberReferences(fileNumber);
         updateFilePreviews();
     };
 
@@ -113,7 +143,7 @@ async function showFilePreview(file, fileNumber) {
         else if (file.type.includes("json")) iconName = 'html';
         else if (file.type.includes("zip")) iconName = 'folder_zip';
         // Set HTML
-        thumbnailSpan.innerHTML = '<i class="material-symbols-outlined preview-icon">' + iconName + '</i>';
+        thumbnailSpan.innerHTML = '<i class="material-symbols-outlined preview-icon" aria-hidden="true">' + iconName + '</i>';
     }
 
     previewDiv.appendChild(closeButton);
@@ -167,6 +197,92 @@ async function updateFilePreviews() {
     }
 }
 
+// Stage the files as attachments of the next entry
+// With `insertReferences`, `%N` is put at the cursor for each file
+async function stageFiles(files, insertReferences = false) {
+    if (insertReferences) {
+        const references = files.map((file, i) => `%${fileList.length + i + 1}`);
+        window.easyMDE.codemirror.replaceSelection(references.join(" "));
+        window.easyMDE.codemirror.focus();
+    }
+    fileList = fileList.concat(files);
+    await updateFilePreviews();
+}
+
+// The files after a removed one move up, so the `%N` references in the text follow:
+// those of the removed file are dropped, and the later ones renumbered
+function renumberReferences(removed) {
+    const renumber = (n) => (n > removed ? n - 1 : n);
+    const codemirror = window.easyMDE.codemirror;
+    const cursor = codemirror.getCursor();
+    const text = codemirror.getValue()
+        // %{N options}
+        .replace(/%\{(\s*)(\d+)(\s[^}\n]*)?\}/g, (match, space, n, options) =>
+            Number(n) === removed ? "" : `%{${space}${renumber(Number(n))}${options || ""}}`)
+        // %N
+        .replace(/%(\d+)/g, (match, n) =>
+            Number(n) === removed ? "" : `%${renumber(Number(n))}`);
+    if (text !== codemirror.getValue()) {
+        codemirror.setValue(text);
+        codemirror.setCursor(cursor);
+    }
+}
+
+// Pick up the files from the clipboard or drag & drop data
+function filesFromTransfer(transfer, fromClipboard = false) {
+    if (!transfer) return [];
+    let files = [];
+    if (transfer.items && transfer.items.length > 0) {
+        for (const item of transfer.items) {
+            if (item.kind === "file") {
+                const file = item.getAsFile();
+                if (file) files.push(file);
+            }
+        }
+    } else if (transfer.files) {
+        files = Array.from(transfer.files);
+    }
+    return fromClipboard ? files.map(renamePastedFile) : files;
+}
+
+// Screenshots from the clipboard are all called "image.png", so give them a unique name
+// Only for pasted files: a dropped or picked file keeps the name it has on the disk
+function renamePastedFile(file) {
+    if (file.name && file.name !== "image.png") return file;
+    const now = new Date();
+    const pad = (n) => String(n).padStart(2, "0");
+    const stamp = `${now.getFullYear()}${pad(now.getMonth() + 1)}${pad(now.getDate())}`
+        + `-${pad(now.getHours())}${pad(now.getMinutes())}${pad(now.getSeconds())}`;
+    const extension = file.type.startsWith("image/") ? file.type.split("/")[1] : "bin";
+    return new File([file], `pasted-${stamp}.${extension}`, { type: file.type });
+}
+
+// Drag & drop onto the timeline
+function initialiseTimelineDrop() {
+    const hasFiles = (event) =>
+        event.dataTransfer && Array.from(event.dataTransfer.types).includes("Files");
+
+    window.content.addEventListener("dragover", function (event) {
+        if (!hasFiles(event)) return;
+        event.preventDefault();
+        event.dataTransfer.dropEffect = "copy";
+        window.content.classList.add("drop-target");
+    });
+    window.content.addEventListener("dragleave", function (event) {
+        if (!window.content.contains(event.relatedTarget)) {
+            window.content.classList.remove("drop-target");
+        }
+    });
+    window.content.addEventListener("drop", function (event) {
+        window.content.classList.remove("drop-target");
+        const files = filesFromTransfer(event.dataTransfer);
+        if (files.length > 0) {
+            event.preventDefault();
+            stageFiles(files, true);
+        }
+    });
+}
+
 // Attach files 
 async function attachFile() {
     const input = document.createElement("input");
@@ -174,9 +290,7 @@ async function attachFile() {
     input.multiple = true;
 
     input.onchange = async function (event) {
-        const newFiles = Array.from(event.target.files);
-        fileList = fileList.concat(newFiles);
-        await updateFilePreviews();
+        await stageFiles(Array.from(event.target.files));
     };
     // File selector
     input.click();
@@ -192,9 +306,27 @@ function addEntry() {
         files = fileList;
     }
 
-    // Send to the Rust side
+    // Send to the Rust side, which calls clearEditor() once it is accepted
     window.send_add_entry(markdownContent, files);
-    // Clear
+}
+
+// Focus to start typing, e.g. a reply
+function focusEditor() {
+    if (window.easyMDE) {
+        window.easyMDE.codemirror.focus();
+    }
+}
+
+// The placeholder and the label in the language of the UI, once switched
+function setEditorPlaceholder(text, label) {
+    if (window.easyMDE) {
+        window.easyMDE.codemirror.setOption("placeholder", text);
+        window.easyMDE.codemirror.getInputField().setAttribute("aria-label", label);
+    }
+}
+
+// Clear
+function clearEditor() {
     window.easyMDE.value("");
     window.filePreviews.innerHTML = "";
     fileList = [];
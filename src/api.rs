@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+
+use anyhow::Error;
+use gloo_net::http::Request;
+use serde::Deserialize;
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::Msg;
+
+// Version of the backend API this frontend is written for
+pub const API_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    api_version: u32,
+}
+
+// A page of entries, the latest first
+// Records which cannot be read are kept as placeholders (see. models.rs)
+pub async fn fetch_page(client_hash: &str, limit: i64, offset: i64) -> Result<Vec<Entry>, Error> {
+    let request = format!(
+        "http://127.0.0.1:8080/get_entries?client={}&limit={}&offset={}",
+        client_hash, limit, offset
+    );
+    let response = Request::get(&request).send().await?;
+    if !response.ok() {
+        anyhow::bail!(
+            "get_entries failed: {} {}",
+            response.status(),
+            response.status_text()
+        );
+    }
+    let records = response.json::<Vec<serde_json::Value>>().await?;
+    Ok(parse_page(records, offset))
+}
+
+// The entries logged since the `known` ones were loaded, the latest first
+// Paged back until a page reaches a known entry, or the beginning of the logbook
+pub async fn fetch_new(
+    client_hash: &str,
+    limit: i64,
+    known: &HashSet<String>,
+) -> Result<Vec<Entry>, Error> {
+    let mut new = vec![];
+    loop {
+        let page = fetch_page(client_hash, limit, new.len() as i64).await?;
+        let last = (page.len() as i64) < limit;
+        match page.iter().position(|entry| known.contains(&entry.id)) {
+            Some(i) => {
+                new.extend(page.into_iter().take(i));
+                return Ok(new);
+            }
+            None if last => {
+                new.extend(page);
+                return Ok(new);
+            }
+            None => new.extend(page),
+        }
+    }
+}
+
+// Warning to show when the backend speaks another version of the API
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiWarning {
+    // HTTP status of the version request
+    NoVersion(u16),
+    Unreachable(String),
+    // Version spoken by the backend
+    Mismatch(u32),
+    InvalidVersion(String),
+}
+
+impl ApiWarning {
+    pub fn message(&self, locale: Locale) -> String {
+        match self {
+            ApiWarning::NoVersion(status) => {
+                format!("{} (HTTP {})", locale.text(Text::ApiNoVersion), status)
+            }
+            ApiWarning::Unreachable(err) => {
+                format!("{}: {}", locale.text(Text::ApiUnreachable), err)
+            }
+            ApiWarning::Mismatch(version) => format!(
+                "{} (API {} ≠ {})",
+                locale.text(Text::ApiMismatch),
+                version,
+                API_VERSION
+            ),
+            ApiWarning::InvalidVersion(err) => {
+                format!("{}: {}", locale.text(Text::ApiInvalidVersion), err)
+            }
+        }
+    }
+}
+
+pub async fn check_api_version() -> Option<ApiWarning> {
+    let response = match Request::get("http://127.0.0.1:8080/version").send().await {
+        Ok(response) if response.ok() => response,
+        Ok(response) => return Some(ApiWarning::NoVersion(response.status())),
+        Err(err) => return Some(ApiWarning::Unreachable(err.to_string())),
+    };
+    match response.json::<VersionResponse>().await {
+        Ok(version) if version.api_version == API_VERSION => None,
+        Ok(version) => Some(ApiWarning::Mismatch(version.api_version)),
+        Err(err) => Some(ApiWarning::InvalidVersion(err.to_string())),
+    }
+}
+
+impl Model {
+    // Banner in the header when the backend does not match
+    pub fn api_banner(&self, ctx: &Context<Self>) -> Html {
+        let Some(warning) = &self.api_warning else {
+            return html! {};
+        };
+        html! {
+            <div class="api-banner">
+                <span>{ warning.message(self.locale) }</span>
+                <button class="close-button" aria-label={self.t(Text::Close)} onclick={ctx.link().callback(|_| Msg::DismissApiWarning)}>
+                    {"×"}
+                </button>
+            </div>
+        }
+    }
+
+    // Placeholder of a record which could not be read, in place of the entry
+    pub fn malformed_entry(&self, entry: &Entry, error: &str) -> Html {
+        html! {
+            <article id={format!("entry-{}", entry.id)} class="entry-item malformed-entry" tabindex="-1">
+                <span class="timestamp">{"?"}</span>
+                <div class="log-text">
+                    <span class="malformed-error">
+                        <i class="material-symbols-outlined" aria-hidden="true">{"warning"}</i>
+                        { format!("{}: {}", self.t(Text::MalformedEntry), error) }
+                    </span>
+                    <details>
+                        <summary>{ self.t(Text::RawData) }</summary>
+                        <pre class="malformed-raw">{ &entry.log }</pre>
+                    </details>
+                </div>
+            </article>
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+
+use anyhow::Error;
+use chrono::Utc;
+use gloo_net::http::Request;
+use serde::Serialize;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::export::*;
+use crate::models::*;
+use crate::preview::*;
+use crate::references::*;
+use crate::templates::*;
+use crate::timezone::*;
+
+// Entries as markdown files with their attachments, to be read without the logbook
+//   <root>/manifest.json
+//   <root>/entries/<date>_<time>_<id>.md
+//   <root>/attachments/<entry id>/<N>_<name>
+pub struct Archive {
+    root: String,
+    // Completed with the attachments which could not be downloaded
+    manifest: serde_json::Value,
+    // Files made from the entries, by path in the archive
+    files: Vec<(String, Vec<u8>)>,
+    // Attachments to download, by path in the archive
+    downloads: Vec<(String, String)>,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    title: &'a str,
+    from: &'a str,
+    to: &'a str,
+    tags: Vec<String>,
+    generated: String,
+    entries: Vec<ManifestEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry<'a> {
+    id: &'a str,
+    // Time of the event, and of the submission
+    timestamp: String,
+    created_at: String,
+    author: Option<&'a str>,
+    severity: &'static str,
+    tags: &'a [String],
+    parent: Option<&'a str>,
+    fields: Option<&'a EntryFields>,
+    file: String,
+    attachments: Vec<ManifestAttachment<'a>>,
+}
+
+#[derive(Serialize)]
+struct ManifestAttachment<'a> {
+    id: u32,
+    name: &'a str,
+    mime: &'a str,
+    size: Option<u64>,
+    file: String,
+}
+
+// Content of the attachment, failing on an error status too
+async fn download(url: &str) -> Result<Vec<u8>, Error> {
+    let response = Request::get(url).send().await?;
+    if !response.ok() {
+        anyhow::bail!("{} {}", response.status(), response.status_text());
+    }
+    Ok(response.binary().await?)
+}
+
+// Mark the attachment archived at the path as missing, with the reason
+fn record_failure(manifest: &mut serde_json::Value, path: &str, error: String) {
+    let attachment = manifest["entries"]
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry["attachments"].as_array_mut())
+        .flatten()
+        .find(|attachment| attachment["file"] == path);
+    if let Some(attachment) = attachment {
+        attachment["error"] = error.into();
+    }
+}
+
+impl Archive {
+    pub fn filename(&self) -> String {
+        format!("{}.zip", self.root)
+    }
+
+    // Download the attachments and zip everything
+    // An attachment which cannot be downloaded is left out and noted in the manifest
+    pub async fn build(mut self) -> Result<Vec<u8>, Error> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for (path, content) in self.files.iter() {
+            zip.start_file(format!("{}/{}", self.root, path), options)?;
+            zip.write_all(content)?;
+        }
+        for (path, url) in self.downloads.iter() {
+            match download(url).await {
+                Ok(content) => {
+                    zip.start_file(format!("{}/{}", self.root, path), options)?;
+                    zip.write_all(&content)?;
+                }
+                Err(err) => record_failure(&mut self.manifest, path, err.to_string()),
+            }
+        }
+
+        // Last, once the missing attachments are known
+        zip.start_file(format!("{}/manifest.json", self.root), options)?;
+        zip.write_all(&serde_json::to_vec_pretty(&self.manifest)?)?;
+
+        Ok(zip.finish()?.into_inner())
+    }
+}
+
+// Keep the file names portable
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+// The path as a link destination, with everything but the unreserved characters
+// percent-encoded, e.g. the spaces and the parentheses of the file names
+pub fn link_path(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn entry_file(entry: &Entry, zone: DisplayZone) -> String {
+    format!(
+        "{}_{}.md",
+        zone.format(&entry.timestamp, "%Y-%m-%d_%H%M%S"),
+        file_name(&entry.id)
+    )
+}
+
+fn attachment_file(entry: &Entry, attachment: &Attachment) -> String {
+    format!(
+        "attachments/{}/{}_{}",
+        file_name(&entry.id),
+        attachment.id,
+        file_name(&attachment.original_name)
+    )
+}
+
+impl Model {
+    // Archive of the entries selected in the export dialog
+    pub fn archive(&self, entries: &[Entry], options: &ExportOptions) -> Result<Archive, String> {
+        let entries = options.select(entries, self.zone, self.locale)?;
+        let files: HashMap<&str, String> = entries
+            .iter()
+            .map(|entry| (entry.id.as_str(), entry_file(entry, self.zone)))
+            .collect();
+
+        let mut archive = Archive {
+            root: options.basename(),
+            manifest: serde_json::Value::Null,
+            files: vec![],
+            downloads: vec![],
+        };
+        let mut manifest = Manifest {
+            title: &options.title,
+            from: &options.from,
+            to: &options.to,
+            tags: options.tag_list(),
+            generated: Utc::now().to_rfc3339(),
+            entries: vec![],
+        };
+
+        for entry in entries.iter() {
+            let file = files[entry.id.as_str()].clone();
+            archive.files.push((
+                format!("entries/{}", file),
+                entry_markdown(entry, &files, self.zone).into_bytes(),
+            ));
+
+            let mut attachments = vec![];
+            for attachment in entry.attachments.iter() {
+                let path = attachment_file(entry, attachment);
+                archive
+                    .downloads
+                    .push((path.clone(), self.download_url(attachment)));
+                attachments.push(ManifestAttachment {
+                    id: attachment.id,
+                    name: &attachment.original_name,
+                    mime: &attachment.mime,
+                    size: attachment.size,
+                    file: path,
+                });
+            }
+
+            manifest.entries.push(ManifestEntry {
+                id: &entry.id,
+                timestamp: entry.timestamp.to_rfc3339(),
+                created_at: entry.created_at.to_rfc3339(),
+                author: entry.author.as_deref(),
+                severity: entry.severity.as_str(),
+                tags: &entry.tags,
+                parent: entry.parent.as_deref(),
+                fields: entry.fields.as_ref(),
+                file: format!("entries/{}", file),
+                attachments,
+            });
+        }
+
+        archive.manifest = serde_json::to_value(&manifest).map_err(|err| err.to_string())?;
+        Ok(archive)
+    }
+}
+
+// The entry as a markdown file, with the references pointing at the archived files
+// `files` maps the archived entries to their files, for the links between them
+fn entry_markdown(entry: &Entry, files: &HashMap<&str, String>, zone: DisplayZone) -> String {
+    let link = |attachment: &Attachment, image: bool| {
+        let path = link_path(&format!("../{}", attachment_file(entry, attachment)));
+        if image {
+            format!("![{}]({})", attachment.original_name, path)
+        } else {
+            format!("[{}]({})", attachment.original_name, path)
+        }
+    };
+
+    // With the offset, so that the time stays absolute
+    let mut markdown = format!(
+        "# {}\n\n",
+        zone.format(&entry.timestamp, "%Y-%m-%d %H:%M:%S %:z")
+    );
+    if entry.backdated() {
+        markdown.push_str(&format!(
+            "- Logged: {}\n",
+            zone.format(&entry.created_at, "%Y-%m-%d %H:%M:%S %:z")
+        ));
+    }
+    if let Some(author) = &entry.author {
+        markdown.push_str(&format!("- Author: {}\n", author));
+    }
+    markdown.push_str(&format!("- Severity: {}\n", entry.severity.label()));
+    if !entry.tags.is_empty() {
+        let tags: Vec<String> = entry.tags.iter().map(|tag| format!("#{}", tag)).collect();
+        markdown.push_str(&format!("- Tags: {}\n", tags.join(" ")));
+    }
+    if let Some(parent) = &entry.parent {
+        match files.get(parent.as_str()) {
+            Some(file) => {
+                markdown.push_str(&format!("- Reply to: [{}]({})\n", parent, link_path(file)))
+            }
+            None => markdown.push_str(&format!("- Reply to: {}\n", parent)),
+        }
+    }
+    markdown.push('\n');
+
+    let mut referenced = vec![];
+    for piece in split_references(&entry.log, &entry.attachments) {
+        match piece {
+            Piece::Text(text) => markdown.push_str(&text),
+            Piece::EntryLink(body) => {
+                let (id, label) = match body.split_once('|') {
+                    Some((id, label)) => (id.trim(), label.trim().to_string()),
+                    None => (body.trim(), format!("entry {}", body.trim())),
+                };
+                match files.get(id) {
+                    Some(file) => markdown.push_str(&format!("[{}]({})", label, link_path(file))),
+                    None => markdown.push_str(&format!("[[entry:{}]]", body)),
+                }
+            }
+            Piece::Attachment(_, Ok((attachment, options))) => {
+                let image = matches!(
+                    PreviewKind::of(attachment),
+                    PreviewKind::Image | PreviewKind::Svg
+                );
+                markdown.push_str(&link(attachment, image && !options.link_only));
+                referenced.push(attachment.id);
+            }
+            Piece::Attachment(source, Err(_)) => markdown.push_str(&source),
+            Piece::Plot(Ok((attachment, _))) => {
+                markdown.push_str(&format!("\nChart of {}\n\n", link(attachment, false)));
+                referenced.push(attachment.id);
+            }
+            Piece::Plot(Err(err)) => markdown.push_str(&format!("\n(chart: {})\n\n", err)),
+        }
+    }
+
+    // Attachments not referenced from the text
+    let others: Vec<&Attachment> = entry
+        .attachments
+        .iter()
+        .filter(|attachment| !referenced.contains(&attachment.id))
+        .collect();
+    if !others.is_empty() {
+        markdown.push_str("\n\n## Attachments\n\n");
+        for attachment in others {
+            markdown.push_str(&format!("- {}\n", link(attachment, false)));
+        }
+    }
+
+    markdown
+}
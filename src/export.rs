@@ -0,0 +1,370 @@
+use anyhow::Error;
+use chrono::{Duration, NaiveDate, Utc};
+use html_escape::encode_text;
+use wasm_bindgen::prelude::*;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::api::*;
+use crate::i18n::*;
+use crate::models::*;
+use crate::references::*;
+use crate::tags::*;
+use crate::timezone::*;
+use crate::Msg;
+
+// Entries fetched at once while collecting a report
+const EXPORT_PAGE_SIZE: i64 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    // Self-contained HTML file
+    Html,
+    // Print layout, saved as PDF by the browser
+    Pdf,
+    // Markdown files and attachments in a zip (see. archive.rs)
+    Archive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportField {
+    Title,
+    From,
+    To,
+    Tags,
+}
+
+// Settings of the export dialog
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    // Name of the logbook, heading the report
+    pub title: String,
+    // Date range as typed (YYYY-MM-DD), both ends included
+    pub from: String,
+    pub to: String,
+    // Only the entries having any of these tags, if any
+    pub tags: String,
+    pub exporting: bool,
+    pub error: Option<String>,
+}
+
+impl ExportOptions {
+    // The last week in the display zone
+    pub fn new(zone: DisplayZone) -> Self {
+        let today = zone.today();
+        Self {
+            title: "Elogbook".to_string(),
+            from: (today - Duration::days(6)).format("%Y-%m-%d").to_string(),
+            to: today.format("%Y-%m-%d").to_string(),
+            tags: String::new(),
+            exporting: false,
+            error: None,
+        }
+    }
+
+    pub fn set(&mut self, field: ExportField, value: String) {
+        match field {
+            ExportField::Title => self.title = value,
+            ExportField::From => self.from = value,
+            ExportField::To => self.to = value,
+            ExportField::Tags => self.tags = value,
+        }
+        self.error = None;
+    }
+
+    pub fn range(&self, locale: Locale) -> Result<(NaiveDate, NaiveDate), String> {
+        let parse = |date: &str| {
+            NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("{} (YYYY-MM-DD): {}", locale.text(Text::NotADate), date))
+        };
+        let (from, to) = (parse(&self.from)?, parse(&self.to)?);
+        if from > to {
+            return Err(locale.text(Text::RangeReversed).to_string());
+        }
+        Ok((from, to))
+    }
+
+    pub fn tag_list(&self) -> Vec<String> {
+        parse_tag_list(&self.tags)
+    }
+
+    // The entries in the range having the tags, the days taken in the zone
+    pub fn select<'a>(
+        &self,
+        entries: &'a [Entry],
+        zone: DisplayZone,
+        locale: Locale,
+    ) -> Result<Vec<&'a Entry>, String> {
+        let (from, to) = self.range(locale)?;
+        let tags = self.tag_list();
+        Ok(entries
+            .iter()
+            .filter(|entry| {
+                let date = zone.date(&entry.timestamp);
+                (from..=to).contains(&date)
+                    && (tags.is_empty() || tags.iter().any(|tag| entry.tags.contains(tag)))
+            })
+            .collect())
+    }
+
+    // File name of the export, without the extension
+    pub fn basename(&self) -> String {
+        let name: String = self
+            .title
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("{}_{}_{}", name, self.from, self.to)
+    }
+}
+
+// All the entries back to the date, in the chronological order
+// The backend pages from the latest entry, so the loaded timeline is not enough
+pub async fn fetch_entries_since(
+    client_hash: String,
+    from: NaiveDate,
+    zone: DisplayZone,
+) -> Result<Vec<Entry>, Error> {
+    let mut entries = vec![];
+    let mut offset = 0;
+    loop {
+        let page = fetch_page(&client_hash, EXPORT_PAGE_SIZE, offset).await?;
+        let count = page.len() as i64;
+        // Pages go by the submission time, never earlier than the event time
+        let reached = page
+            .iter()
+            .any(|entry| entry.malformed.is_none() && zone.date(&entry.created_at) < from);
+        // Placeholders of malformed records are left out of the reports
+        entries.extend(page.into_iter().filter(|entry| entry.malformed.is_none()));
+        if count < EXPORT_PAGE_SIZE || reached {
+            break;
+        }
+        offset += count;
+    }
+    entries.sort_by_key(|entry| entry.timestamp);
+    Ok(entries)
+}
+
+impl Model {
+    // Standalone document of the entries in the range
+    // The attachment previews are embedded afterwards by exportReport (export.js)
+    pub fn report_html(
+        &self,
+        entries: &[Entry],
+        options: &ExportOptions,
+    ) -> Result<String, String> {
+        let (from, to) = options.range(self.locale)?;
+        let entries = options.select(entries, self.zone, self.locale)?;
+
+        let mut body = String::new();
+        let mut last_date = None;
+        for entry in entries.iter() {
+            let date = self.zone.date(&entry.timestamp);
+            if last_date != Some(date) {
+                if last_date.is_some() {
+                    body.push_str("</section>\n");
+                }
+                body.push_str(&format!(
+                    "<section class='report-day'>\n<h2>{}</h2>\n",
+                    encode_text(&self.locale.date(date))
+                ));
+                last_date = Some(date);
+            }
+            body.push_str(&self.report_entry_html(entry));
+        }
+        if last_date.is_some() {
+            body.push_str("</section>\n");
+        } else {
+            body.push_str(&format!(
+                "<p class='report-empty'>{}</p>\n",
+                self.t(Text::NoEntriesInRange)
+            ));
+        }
+
+        let tags = options.tag_list();
+        let mut summary = format!(
+            "{} – {} · {} {} · {}",
+            from.format("%Y-%m-%d"),
+            to.format("%Y-%m-%d"),
+            entries.len(),
+            self.t(Text::EntryCount),
+            self.zone.label(self.locale)
+        );
+        if !tags.is_empty() {
+            let tags: Vec<String> = tags.iter().map(|tag| format!("#{}", tag)).collect();
+            summary.push_str(&format!(" · {}", tags.join(" ")));
+        }
+
+        Ok(format!(
+            "<!DOCTYPE html>\n<html lang='{lang}'>\n<head>\n<meta charset='UTF-8'>\n<title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n<header class='report-header'>\n<h1>{title}</h1>\n<p>{summary}</p>\n<p class='report-generated'>{generated_label} {generated}</p>\n</header>\n{body}</body>\n</html>\n",
+            lang = self.locale.as_str(),
+            title = encode_text(&options.title),
+            style = REPORT_STYLE,
+            summary = encode_text(&summary),
+            generated_label = self.t(Text::Generated),
+            generated = self.zone.format(&Utc::now(), "%Y-%m-%d %H:%M"),
+            body = body,
+        ))
+    }
+
+    fn report_entry_html(&self, entry: &Entry) -> String {
+        let rendered = self.rendered_entry(entry);
+
+        let mut meta = format!(
+            "<span class='report-time' title='{}'>{}</span>",
+            utc_title(&entry.timestamp),
+            self.zone.format(&entry.timestamp, "%H:%M:%S")
+        );
+        if let Some(author) = &entry.author {
+            meta.push_str(&format!(
+                "<span class='report-author'>{}</span>",
+                encode_text(author)
+            ));
+        }
+        meta.push_str(&format!(
+            "<span class='report-severity {}'>{}</span>",
+            entry.severity.as_str(),
+            entry.severity.label()
+        ));
+
+        // Attachments not shown in the text come after it
+        let others: String = entry
+            .attachments
+            .iter()
+            .filter(|attachment| !rendered.referenced.contains(&attachment.id))
+            .map(|attachment| {
+                format!(
+                    "<div class='report-attachment'>{}</div>",
+                    self.expand_attachment_html(attachment, &RefOptions::default())
+                )
+            })
+            .collect();
+
+        let tags: String = entry
+            .tags
+            .iter()
+            .map(|tag| format!("<span class='report-tag'>#{}</span>", encode_text(tag)))
+            .collect();
+
+        format!(
+            "<article class='report-entry {}'>\n<div class='report-meta'>{}</div>\n<div class='report-body'>{}</div>\n{}<div class='report-tags'>{}</div>\n</article>\n",
+            entry.severity.as_str(),
+            meta,
+            rendered.html,
+            others,
+            tags
+        )
+    }
+
+    // Dialog choosing what to export
+    pub fn export_dialog(&self, ctx: &Context<Self>) -> Html {
+        let Some(options) = self.export.as_ref() else {
+            return html! {};
+        };
+
+        let input = |field: ExportField, input_type: &'static str, value: &str| {
+            let oninput = ctx.link().callback(move |e: InputEvent| {
+                Msg::UpdateExport(field, e.target_unchecked_into::<HtmlInputElement>().value())
+            });
+            html! { <input type={input_type} value={value.to_string()} {oninput}/> }
+        };
+
+        html! {
+            <div class="dialog-backdrop">
+                <div class="dialog export-dialog" role="dialog" aria-modal="true" aria-label={self.t(Text::ExportReport)}>
+                    <div class="dialog-header">
+                        <span>{ self.t(Text::ExportReport) }</span>
+                        <button class="close-button" aria-label={self.t(Text::Close)} onclick={ctx.link().callback(|_| Msg::OpenExport(false))}>
+                            {"×"}
+                        </button>
+                    </div>
+                    <label>
+                        <span>{ self.t(Text::ExportTitle) }</span>
+                        { input(ExportField::Title, "text", &options.title) }
+                    </label>
+                    <label>
+                        <span>{ self.t(Text::From) }</span>
+                        { input(ExportField::From, "date", &options.from) }
+                    </label>
+                    <label>
+                        <span>{ self.t(Text::To) }</span>
+                        { input(ExportField::To, "date", &options.to) }
+                    </label>
+                    <label>
+                        <span>{ self.t(Text::Tags) }</span>
+                        { input(ExportField::Tags, "text", &options.tags) }
+                    </label>
+                    if let Some(error) = &options.error {
+                        <div class="dialog-error" role="alert">{ error }</div>
+                    }
+                    <div class="dialog-actions">
+                        <button
+                            disabled={options.exporting}
+                            onclick={ctx.link().callback(|_| Msg::Export(ReportFormat::Html))}
+                        >
+                            {"HTML"}
+                        </button>
+                        <button
+                            disabled={options.exporting}
+                            onclick={ctx.link().callback(|_| Msg::Export(ReportFormat::Pdf))}
+                        >
+                            { self.t(Text::PrintPdf) }
+                        </button>
+                        <button
+                            disabled={options.exporting}
+                            onclick={ctx.link().callback(|_| Msg::Export(ReportFormat::Archive))}
+                        >
+                            { self.t(Text::MarkdownArchive) }
+                        </button>
+                    </div>
+                    if options.exporting {
+                        <div class="dialog-status">{ self.t(Text::CollectingEntries) }</div>
+                    }
+                </div>
+            </div>
+        }
+    }
+}
+
+// Functions of Java Script
+#[wasm_bindgen]
+extern "C" {
+    // Embed the attachments and save or print the report (export.js)
+    // Resolves when done, rejects with an error message
+    #[wasm_bindgen(js_name = exportReport)]
+    pub fn export_report(html: &str, filename: &str, print: bool) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_name = saveFile)]
+    pub fn save_file(data: &[u8], filename: &str, mime: &str);
+}
+
+// Layout of the report, also for printing
+const REPORT_STYLE: &str = r#"
+body { font-family: sans-serif; color: #222; max-width: 60em; margin: 2em auto; padding: 0 1em; line-height: 1.5; }
+.report-header { border-bottom: 2px solid #444; margin-bottom: 1.5em; }
+.report-header h1 { margin: 0 0 0.2em; }
+.report-header p { margin: 0.2em 0; color: #555; }
+.report-generated { font-size: 0.85em; }
+.report-day h2 { font-size: 1.2em; border-bottom: 1px solid #ccc; padding-bottom: 0.2em; margin-top: 1.5em; }
+.report-entry { padding: 0.5em 0 0.5em 0.8em; border-left: 4px solid #ccc; margin: 0.8em 0; break-inside: avoid; }
+.report-entry.warning { border-left-color: #e0a800; }
+.report-entry.problem { border-left-color: #e8590c; }
+.report-entry.critical { border-left-color: #c92a2a; }
+.report-meta { font-size: 0.85em; color: #555; display: flex; gap: 0.8em; }
+.report-time { font-family: monospace; }
+.report-severity { text-transform: uppercase; font-weight: bold; }
+.report-body img, .report-attachment img { max-width: 100%; }
+.report-body pre { background: #f4f4f4; padding: 0.5em; overflow-x: auto; white-space: pre-wrap; }
+.report-body table { border-collapse: collapse; }
+.report-body td, .report-body th { border: 1px solid #ccc; padding: 0.2em 0.5em; }
+.report-file { display: inline-block; font-size: 0.85em; color: #555; border: 1px solid #ccc; border-radius: 3px; padding: 0 0.4em; }
+.report-tags { font-size: 0.85em; color: #1c6cb0; }
+.report-tag { margin-right: 0.5em; }
+.report-empty { color: #777; }
+@media print {
+  body { margin: 0; max-width: none; font-size: 10pt; }
+  .report-day h2 { break-after: avoid; }
+  a { color: inherit; text-decoration: none; }
+  @page { margin: 15mm; }
+}
+"#;
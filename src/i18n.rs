@@ -0,0 +1,647 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+use crate::keyboard::*;
+use crate::models::*;
+use crate::notifications::*;
+use crate::preferences::*;
+use crate::severity::*;
+use crate::Msg;
+
+// Language of the UI
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Locale {
+    #[default]
+    English,
+    Japanese,
+}
+
+// Strings of the UI, looked up in the catalog of the locale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Text {
+    Title,
+    Language,
+    DisplaySettings,
+    KeyboardShortcuts,
+    Import,
+    ImportEntries,
+    Export,
+    ExportReport,
+    LoadingOlder,
+    BeginningOfLogbook,
+    EditorPlaceholder,
+    NoTemplate,
+    EventTime,
+    EntryNotSent,
+    TagsPlaceholder,
+    Submitted,
+    LoadingChart,
+    LoadingImage,
+    LoadingPdf,
+    LoadingTable,
+    LoadingPreview,
+    Download,
+    Close,
+    Timeline,
+    Filters,
+    Search,
+    NewEntry,
+    Template,
+    Severity,
+    Tags,
+    TimeZone,
+    EntryAdded,
+    NewEntries,
+    Notifications,
+    EntryNotFound,
+    // Names of the values, which stay in English in the exported files
+    SeverityName(Severity),
+    CommandName(Command),
+    RuleKindName(RuleKind),
+    RulePlaceholder(RuleKind),
+    ThemeName(Theme),
+    DensityName(Density),
+    // Export
+    ExportTitle,
+    From,
+    To,
+    PrintPdf,
+    MarkdownArchive,
+    CollectingEntries,
+    // Import
+    ReadingFiles,
+    Time,
+    Entry,
+    Attachments,
+    EntryCount,
+    AttachmentCount,
+    Importing,
+    Imported,
+    Pending,
+    // Severities and tags
+    Acknowledge,
+    AcknowledgeAll,
+    MatchAny,
+    MatchAll,
+    Clear,
+    // Display settings
+    Display,
+    Theme,
+    Density,
+    FontSize,
+    // Command palette and the key bindings
+    SubmitEntry,
+    TypeCommand,
+    DateFormat,
+    GoTo,
+    EnterDate,
+    // Records which cannot be read
+    MalformedEntry,
+    RawData,
+    // Notifications
+    NotificationsBlocked,
+    NotificationsNotAllowed,
+    AllowNotifications,
+    OrHigher,
+    By,
+    NoRules,
+    Mute,
+    Unmute,
+    Remove,
+    RuleKind,
+    Add,
+    // Threads
+    Reply,
+    ReplyTo,
+    ReplyToEarlier,
+    ReplyingTo,
+    ReplyCountOne,
+    ReplyCount,
+    SearchPlaceholder,
+    // Dates and times
+    NotADate,
+    NoEntriesShown,
+    OlderNotLoaded,
+    LoadedBackTo,
+    ZoneBrowser,
+    ZoneSite,
+    Logged,
+    NotATime,
+    NoSuchTime,
+    EventInFuture,
+    RangeReversed,
+    NoEntriesInRange,
+    Generated,
+    // Validation of the template fields
+    Required,
+    NotANumber,
+    AtLeast,
+    AtMost,
+    NotAChoice,
+    // Backend
+    ApiNoVersion,
+    ApiUnreachable,
+    ApiMismatch,
+    ApiInvalidVersion,
+    // Previews and the image viewer, passed to the scripts
+    Submit,
+    Plot,
+    SortColumn,
+    EmptyTable,
+    InvalidSvg,
+    CannotParse,
+    NoNumericColumns,
+    ImageScope,
+    Day,
+    ZoomIn,
+    ZoomOut,
+    FitImage,
+    OpenOriginal,
+    PreviousImage,
+    NextImage,
+}
+
+// Strings of the scripts (textarea.js, preview.js, lightbox.js), read by uiText from the root element
+const SCRIPT_TEXTS: [(&str, Text); 18] = [
+    ("submit", Text::Submit),
+    ("plot", Text::Plot),
+    ("sort", Text::SortColumn),
+    ("empty-table", Text::EmptyTable),
+    ("invalid-svg", Text::InvalidSvg),
+    ("cannot-parse", Text::CannotParse),
+    ("no-numeric-columns", Text::NoNumericColumns),
+    ("image-scope", Text::ImageScope),
+    ("entry", Text::Entry),
+    ("day", Text::Day),
+    ("zoom-in", Text::ZoomIn),
+    ("zoom-out", Text::ZoomOut),
+    ("fit", Text::FitImage),
+    ("open-original", Text::OpenOriginal),
+    ("download", Text::Download),
+    ("close", Text::Close),
+    ("previous", Text::PreviousImage),
+    ("next", Text::NextImage),
+];
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::Japanese];
+
+    // As a language tag, also set to lang of the page
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Japanese => "ja",
+        }
+    }
+
+    // Language tags like "ja-JP" are taken by the language
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.split('-').next()? {
+            "en" => Some(Locale::English),
+            "ja" => Some(Locale::Japanese),
+            _ => None,
+        }
+    }
+
+    // In its own language, to be found by those who cannot read the current one
+    pub fn label(&self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::Japanese => "日本語",
+        }
+    }
+
+    pub fn text(&self, text: Text) -> &'static str {
+        match self {
+            Locale::English => english(text),
+            Locale::Japanese => japanese(text),
+        }
+    }
+
+    // Date of the day separators in the timeline
+    pub fn date(&self, date: NaiveDate) -> String {
+        match self {
+            Locale::English => date.format("%a, %b %-d, %Y").to_string(),
+            Locale::Japanese => {
+                let weekday = match date.weekday() {
+                    Weekday::Mon => "月",
+                    Weekday::Tue => "火",
+                    Weekday::Wed => "水",
+                    Weekday::Thu => "木",
+                    Weekday::Fri => "金",
+                    Weekday::Sat => "土",
+                    Weekday::Sun => "日",
+                };
+                format!(
+                    "{}年{}月{}日 ({})",
+                    date.year(),
+                    date.month(),
+                    date.day(),
+                    weekday
+                )
+            }
+        }
+    }
+
+    // Set to the root element, for the fonts and the screen readers
+    pub fn apply(&self) {
+        if let Some(root) = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.document_element())
+        {
+            let _ = root.set_attribute("lang", self.as_str());
+            for (name, text) in SCRIPT_TEXTS {
+                let _ = root.set_attribute(&format!("data-text-{}", name), self.text(text));
+            }
+        }
+    }
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::Title => "Elogbook Entries",
+        Text::Language => "Language",
+        Text::DisplaySettings => "Display settings",
+        Text::KeyboardShortcuts => "Keyboard shortcuts (?)",
+        Text::Import => "Import",
+        Text::ImportEntries => "Import entries",
+        Text::Export => "Export",
+        Text::ExportReport => "Export report",
+        Text::LoadingOlder => "Loading older entries…",
+        Text::BeginningOfLogbook => "Beginning of the logbook",
+        Text::EditorPlaceholder => "Enter text here...",
+        Text::NoTemplate => "No template",
+        Text::EventTime => "Time of the event, if earlier",
+        Text::EntryNotSent => "The entry was not sent",
+        Text::TagsPlaceholder => "Tags (e.g. rf, beam-loss)",
+        Text::Submitted => "Submitted",
+        Text::LoadingChart => "Loading chart...",
+        Text::LoadingImage => "Loading image preview...",
+        Text::LoadingPdf => "Loading PDF preview...",
+        Text::LoadingTable => "Loading table preview...",
+        Text::LoadingPreview => "Loading preview...",
+        Text::Download => "Download",
+        Text::Close => "Close",
+        Text::Timeline => "Timeline",
+        Text::Filters => "Search and filters",
+        Text::Search => "Search the loaded entries",
+        Text::NewEntry => "New entry",
+        Text::Template => "Template",
+        Text::Severity => "Severity",
+        Text::Tags => "Tags",
+        Text::TimeZone => "Time zone of the displayed times",
+        Text::EntryAdded => "Entry added",
+        Text::NewEntries => "new entries",
+        Text::Notifications => "Notifications",
+        Text::EntryNotFound => "The linked entry was not found in the logbook",
+        Text::SeverityName(severity) => severity.label(),
+        Text::CommandName(command) => match command {
+            Command::NextEntry => "Next entry",
+            Command::PreviousEntry => "Previous entry",
+            Command::LatestEntry => "Latest entry",
+            Command::GoToDate => "Go to date…",
+            Command::FocusSearch => "Search entries",
+            Command::Reply => "Reply to the selected entry",
+            Command::NewEntry => "Write a new entry",
+            Command::OpenAttachments => "Open the attachments of the selected entry",
+            Command::ToggleThread => "Show or hide the replies of the selected entry",
+            Command::ClearFilters => "Clear the search and the filters",
+            Command::Export => "Export report…",
+            Command::Import => "Import entries…",
+            Command::Display => "Display settings…",
+            Command::Notifications => "Notification rules…",
+            Command::OpenPalette => "Command palette",
+            Command::Help => "Keyboard shortcuts",
+            Command::Close => "Close / clear the selection",
+        },
+        Text::RuleKindName(kind) => match kind {
+            RuleKind::Tag => "Tag",
+            RuleKind::Severity => "Severity",
+            RuleKind::Keyword => "Keyword",
+            RuleKind::Author => "Author",
+        },
+        Text::RulePlaceholder(kind) => match kind {
+            RuleKind::Tag => "e.g. beam-loss",
+            RuleKind::Severity => "",
+            RuleKind::Keyword => "e.g. interlock",
+            RuleKind::Author => "Name of the author",
+        },
+        Text::ThemeName(theme) => match theme {
+            Theme::System => "System",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+            Theme::HighContrast => "High contrast",
+        },
+        Text::DensityName(density) => match density {
+            Density::Comfortable => "Comfortable",
+            Density::Compact => "Compact",
+        },
+        Text::ExportTitle => "Logbook",
+        Text::From => "From",
+        Text::To => "To",
+        Text::PrintPdf => "Print / PDF",
+        Text::MarkdownArchive => "Markdown archive (zip)",
+        Text::CollectingEntries => "Collecting entries…",
+        Text::ReadingFiles => "Reading files…",
+        Text::Time => "Time",
+        Text::Entry => "Entry",
+        Text::Attachments => "Attachments",
+        Text::EntryCount => "entries",
+        Text::AttachmentCount => "attachments",
+        Text::Importing => "Importing…",
+        Text::Imported => "Imported",
+        Text::Pending => "Pending",
+        Text::Acknowledge => "Acknowledge",
+        Text::AcknowledgeAll => "Acknowledge all",
+        Text::MatchAny => "OR",
+        Text::MatchAll => "AND",
+        Text::Clear => "Clear",
+        Text::Display => "Display",
+        Text::Theme => "Theme",
+        Text::Density => "Density",
+        Text::FontSize => "Font size",
+        Text::SubmitEntry => "Submit the entry (in the editor)",
+        Text::TypeCommand => "Type a command",
+        Text::DateFormat => "Date (YYYY-MM-DD or MM-DD)",
+        Text::GoTo => "Go to",
+        Text::EnterDate => "Enter a date",
+        Text::MalformedEntry => "Malformed entry from the backend",
+        Text::RawData => "Raw data",
+        Text::NotificationsBlocked => "Notifications are blocked for this page in the browser settings. The unread count is still shown in the title.",
+        Text::NotificationsNotAllowed => "Notifications are not allowed yet.",
+        Text::AllowNotifications => "Allow notifications",
+        Text::OrHigher => "or higher",
+        Text::By => "by",
+        Text::NoRules => "No rules yet. New entries matching a rule are notified while this page is in the background.",
+        Text::Mute => "Mute",
+        Text::Unmute => "Unmute",
+        Text::Remove => "Remove",
+        Text::RuleKind => "Kind of the rule",
+        Text::Add => "Add",
+        Text::Reply => "Reply",
+        Text::ReplyTo => "Reply to",
+        Text::ReplyToEarlier => "Reply to an earlier entry",
+        Text::ReplyingTo => "Replying to:",
+        Text::ReplyCountOne => "reply",
+        Text::ReplyCount => "replies",
+        Text::SearchPlaceholder => "Search (/)",
+        Text::NotADate => "Not a date",
+        Text::NoEntriesShown => "No entries are shown",
+        Text::OlderNotLoaded => "Older entries are not loaded yet, scroll up to load them",
+        Text::LoadedBackTo => "loaded back to",
+        Text::ZoneBrowser => "Browser",
+        Text::ZoneSite => "Site",
+        Text::Logged => "logged",
+        Text::NotATime => "Not a time",
+        Text::NoSuchTime => "No such time in the zone",
+        Text::EventInFuture => "The event time is in the future",
+        Text::RangeReversed => "The range ends before it starts",
+        Text::NoEntriesInRange => "No entries in this range.",
+        Text::Generated => "Generated",
+        Text::Required => "Required",
+        Text::NotANumber => "Not a number",
+        Text::AtLeast => "Must be ≥",
+        Text::AtMost => "Must be ≤",
+        Text::NotAChoice => "Not one of the choices",
+        Text::ApiNoVersion => "The backend does not report its API version. Some features may not work.",
+        Text::ApiUnreachable => "The backend cannot be reached",
+        Text::ApiMismatch => "The backend speaks another version of the API. Some entries may not be shown correctly.",
+        Text::ApiInvalidVersion => "Invalid version response from the backend",
+        Text::Submit => "Submit",
+        Text::Plot => "Plot",
+        Text::SortColumn => "Sort",
+        Text::EmptyTable => "Empty table",
+        Text::InvalidSvg => "Invalid SVG",
+        Text::CannotParse => "Cannot parse",
+        Text::NoNumericColumns => "No numeric columns to plot",
+        Text::ImageScope => "Browse images of the entry or the whole day",
+        Text::Day => "Day",
+        Text::ZoomIn => "Zoom in",
+        Text::ZoomOut => "Zoom out",
+        Text::FitImage => "Fit",
+        Text::OpenOriginal => "Open original",
+        Text::PreviousImage => "Previous",
+        Text::NextImage => "Next",
+    }
+}
+
+fn japanese(text: Text) -> &'static str {
+    match text {
+        Text::Title => "電子ログブック",
+        Text::Language => "言語",
+        Text::DisplaySettings => "表示設定",
+        Text::KeyboardShortcuts => "キーボードショートカット (?)",
+        Text::Import => "インポート",
+        Text::ImportEntries => "エントリーをインポート",
+        Text::Export => "エクスポート",
+        Text::ExportReport => "レポートをエクスポート",
+        Text::LoadingOlder => "過去のエントリーを読み込み中…",
+        Text::BeginningOfLogbook => "ログブックの先頭",
+        Text::EditorPlaceholder => "ここに入力...",
+        Text::NoTemplate => "テンプレートなし",
+        Text::EventTime => "出来事の時刻 (過去の場合)",
+        Text::EntryNotSent => "エントリを送信できませんでした",
+        Text::TagsPlaceholder => "タグ (例: rf, beam-loss)",
+        Text::Submitted => "投稿",
+        Text::LoadingChart => "グラフを読み込み中...",
+        Text::LoadingImage => "画像を読み込み中...",
+        Text::LoadingPdf => "PDFを読み込み中...",
+        Text::LoadingTable => "表を読み込み中...",
+        Text::LoadingPreview => "プレビューを読み込み中...",
+        Text::Download => "ダウンロード",
+        Text::Close => "閉じる",
+        Text::Timeline => "タイムライン",
+        Text::Filters => "検索とフィルター",
+        Text::Search => "読み込んだエントリーを検索",
+        Text::NewEntry => "新しいエントリー",
+        Text::Template => "テンプレート",
+        Text::Severity => "重要度",
+        Text::Tags => "タグ",
+        Text::TimeZone => "表示する時刻のタイムゾーン",
+        Text::EntryAdded => "エントリーを追加しました",
+        Text::NewEntries => "件の新しいエントリー",
+        Text::Notifications => "通知",
+        Text::EntryNotFound => "リンク先のエントリーはログブックにありません",
+        Text::SeverityName(severity) => match severity {
+            Severity::Info => "情報",
+            Severity::Warning => "警告",
+            Severity::Problem => "問題",
+            Severity::Critical => "重大",
+        },
+        Text::CommandName(command) => match command {
+            Command::NextEntry => "次のエントリー",
+            Command::PreviousEntry => "前のエントリー",
+            Command::LatestEntry => "最新のエントリー",
+            Command::GoToDate => "日付へ移動…",
+            Command::FocusSearch => "エントリーを検索",
+            Command::Reply => "選択したエントリーに返信",
+            Command::NewEntry => "新しいエントリーを書く",
+            Command::OpenAttachments => "選択したエントリーの添付ファイルを開く",
+            Command::ToggleThread => "選択したエントリーの返信を表示 / 非表示",
+            Command::ClearFilters => "検索とフィルターを解除",
+            Command::Export => "レポートをエクスポート…",
+            Command::Import => "エントリーをインポート…",
+            Command::Display => "表示設定…",
+            Command::Notifications => "通知ルール…",
+            Command::OpenPalette => "コマンドパレット",
+            Command::Help => "キーボードショートカット",
+            Command::Close => "閉じる / 選択を解除",
+        },
+        Text::RuleKindName(kind) => match kind {
+            RuleKind::Tag => "タグ",
+            RuleKind::Severity => "重要度",
+            RuleKind::Keyword => "キーワード",
+            RuleKind::Author => "投稿者",
+        },
+        Text::RulePlaceholder(kind) => match kind {
+            RuleKind::Tag => "例: beam-loss",
+            RuleKind::Severity => "",
+            RuleKind::Keyword => "例: interlock",
+            RuleKind::Author => "投稿者の名前",
+        },
+        Text::ThemeName(theme) => match theme {
+            Theme::System => "システム",
+            Theme::Light => "ライト",
+            Theme::Dark => "ダーク",
+            Theme::HighContrast => "ハイコントラスト",
+        },
+        Text::DensityName(density) => match density {
+            Density::Comfortable => "標準",
+            Density::Compact => "コンパクト",
+        },
+        Text::ExportTitle => "ログブック",
+        Text::From => "開始日",
+        Text::To => "終了日",
+        Text::PrintPdf => "印刷 / PDF",
+        Text::MarkdownArchive => "Markdown アーカイブ (zip)",
+        Text::CollectingEntries => "エントリーを収集中…",
+        Text::ReadingFiles => "ファイルを読み込み中…",
+        Text::Time => "時刻",
+        Text::Entry => "エントリー",
+        Text::Attachments => "添付ファイル",
+        Text::EntryCount => "件のエントリー",
+        Text::AttachmentCount => "個の添付ファイル",
+        Text::Importing => "インポート中…",
+        Text::Imported => "インポート済み",
+        Text::Pending => "待機中",
+        Text::Acknowledge => "確認",
+        Text::AcknowledgeAll => "すべて確認",
+        Text::MatchAny => "いずれか",
+        Text::MatchAll => "すべて",
+        Text::Clear => "解除",
+        Text::Display => "表示",
+        Text::Theme => "テーマ",
+        Text::Density => "表示密度",
+        Text::FontSize => "文字サイズ",
+        Text::SubmitEntry => "エントリーを投稿 (エディター内)",
+        Text::TypeCommand => "コマンドを入力",
+        Text::DateFormat => "日付 (YYYY-MM-DD または MM-DD)",
+        Text::GoTo => "移動先:",
+        Text::EnterDate => "日付を入力",
+        Text::MalformedEntry => "バックエンドから読み取れないエントリー",
+        Text::RawData => "元のデータ",
+        Text::NotificationsBlocked => "このページの通知はブラウザーの設定でブロックされています。未読数はタイトルに表示されます。",
+        Text::NotificationsNotAllowed => "通知はまだ許可されていません。",
+        Text::AllowNotifications => "通知を許可",
+        Text::OrHigher => "以上",
+        Text::By => "投稿者:",
+        Text::NoRules => "ルールはまだありません。このページがバックグラウンドにある間、ルールに一致する新しいエントリーが通知されます。",
+        Text::Mute => "ミュート",
+        Text::Unmute => "ミュートを解除",
+        Text::Remove => "削除",
+        Text::RuleKind => "ルールの種類",
+        Text::Add => "追加",
+        Text::Reply => "返信",
+        Text::ReplyTo => "返信先:",
+        Text::ReplyToEarlier => "以前のエントリーへの返信",
+        Text::ReplyingTo => "返信先:",
+        Text::ReplyCountOne => "件の返信",
+        Text::ReplyCount => "件の返信",
+        Text::SearchPlaceholder => "検索 (/)",
+        Text::NotADate => "日付ではありません",
+        Text::NoEntriesShown => "表示されているエントリーはありません",
+        Text::OlderNotLoaded => "古いエントリーはまだ読み込まれていません。上にスクロールすると読み込まれます",
+        Text::LoadedBackTo => "読み込み済み:",
+        Text::ZoneBrowser => "ブラウザー",
+        Text::ZoneSite => "サイト",
+        Text::Logged => "投稿",
+        Text::NotATime => "時刻ではありません",
+        Text::NoSuchTime => "このタイムゾーンに存在しない時刻",
+        Text::EventInFuture => "出来事の時刻が未来です",
+        Text::RangeReversed => "終了日が開始日より前です",
+        Text::NoEntriesInRange => "この期間のエントリーはありません。",
+        Text::Generated => "作成",
+        Text::Required => "必須です",
+        Text::NotANumber => "数値ではありません",
+        Text::AtLeast => "最小値:",
+        Text::AtMost => "最大値:",
+        Text::NotAChoice => "選択肢にありません",
+        Text::ApiNoVersion => "バックエンドが API バージョンを返しません。一部の機能が動作しない可能性があります。",
+        Text::ApiUnreachable => "バックエンドに接続できません",
+        Text::ApiMismatch => "バックエンドの API バージョンが異なります。一部のエントリーが正しく表示されない可能性があります。",
+        Text::ApiInvalidVersion => "バックエンドのバージョン応答が不正です",
+        Text::Submit => "投稿",
+        Text::Plot => "グラフ",
+        Text::SortColumn => "並べ替え",
+        Text::EmptyTable => "空の表",
+        Text::InvalidSvg => "不正な SVG",
+        Text::CannotParse => "解析できません",
+        Text::NoNumericColumns => "グラフにできる数値の列がありません",
+        Text::ImageScope => "エントリーまたはその日全体の画像を表示",
+        Text::Day => "日",
+        Text::ZoomIn => "拡大",
+        Text::ZoomOut => "縮小",
+        Text::FitImage => "全体を表示",
+        Text::OpenOriginal => "元の画像を開く",
+        Text::PreviousImage => "前へ",
+        Text::NextImage => "次へ",
+    }
+}
+
+// The language is remembered over reloads, the browser's one until chosen
+const LOCALE_KEY: &str = "elogbook.locale";
+
+pub fn load_locale() -> Locale {
+    let window = web_sys::window();
+    window
+        .as_ref()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(LOCALE_KEY).ok().flatten())
+        .or_else(|| window.and_then(|window| window.navigator().language()))
+        .and_then(|value| Locale::parse(&value))
+        .unwrap_or_default()
+}
+
+pub fn save_locale(locale: Locale) {
+    if let Some(storage) =
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    {
+        let _ = storage.set_item(LOCALE_KEY, locale.as_str());
+    }
+}
+
+impl Model {
+    pub fn t(&self, text: Text) -> &'static str {
+        self.locale.text(text)
+    }
+
+    // Language switcher in the header
+    pub fn locale_selector(&self, ctx: &Context<Self>) -> Html {
+        let onchange = ctx.link().callback(|e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            Msg::SetLocale(Locale::parse(&value).unwrap_or_default())
+        });
+        html! {
+            <select class="zone-select locale-select" title={self.t(Text::Language)} aria-label={self.t(Text::Language)} {onchange}>
+                {
+                    for Locale::ALL.into_iter().map(|locale| html! {
+                        <option value={locale.as_str()} selected={locale == self.locale}>
+                            { locale.label() }
+                        </option>
+                    })
+                }
+            </select>
+        }
+    }
+}
@@ -0,0 +1,803 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+use web_sys::{FormData, HtmlInputElement};
+use yew::prelude::*;
+use zip::ZipArchive;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::severity::*;
+use crate::tags::*;
+use crate::templates::*;
+use crate::Msg;
+
+// A file chosen in the import dialog
+pub struct SourceFile {
+    pub name: String,
+    // Milliseconds since the epoch, the time of the entries without one
+    pub last_modified: f64,
+    pub data: Vec<u8>,
+}
+
+// An attachment to upload with an imported entry
+pub struct ImportedFile {
+    pub name: String,
+    pub mime: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportStatus {
+    Pending,
+    Done,
+    Failed(String),
+}
+
+// An entry read from the imported files, with `%N` pointing at its attachments
+pub struct ImportedEntry {
+    // File it came from
+    pub source: String,
+    pub timestamp: DateTime<Local>,
+    // Submission time, when later than the event
+    pub created: Option<DateTime<Local>>,
+    pub log: String,
+    pub tags: Vec<String>,
+    pub severity: Severity,
+    pub author: Option<String>,
+    pub fields: Option<EntryFields>,
+    pub attachments: Vec<ImportedFile>,
+    // What could not be taken over, e.g. missing files
+    pub warnings: Vec<String>,
+    pub status: ImportStatus,
+}
+
+// State of the import dialog
+#[derive(Default)]
+pub struct ImportState {
+    pub entries: Vec<ImportedEntry>,
+    // Files which could not be read at all
+    pub errors: Vec<String>,
+    pub reading: bool,
+    pub running: bool,
+}
+
+// manifest.json of an archive (see. archive.rs)
+#[derive(Deserialize)]
+struct ArchivedManifest {
+    entries: Vec<ArchivedEntry>,
+}
+
+#[derive(Deserialize)]
+struct ArchivedEntry {
+    timestamp: String,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    severity: Severity,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    fields: Option<EntryFields>,
+    file: String,
+    #[serde(default)]
+    attachments: Vec<ArchivedAttachment>,
+}
+
+#[derive(Deserialize)]
+struct ArchivedAttachment {
+    mime: String,
+    file: String,
+}
+
+// Metadata lines under the heading of an entry, as written by the archive
+#[derive(Default)]
+struct Section {
+    timestamp: Option<DateTime<Local>>,
+    created: Option<DateTime<Local>>,
+    author: Option<String>,
+    severity: Option<Severity>,
+    tags: Vec<String>,
+    body: String,
+}
+
+impl ImportedEntry {
+    pub fn form_data(&self) -> Result<FormData, JsValue> {
+        let form_data = FormData::new()?;
+        form_data.append_with_str("content", &self.log)?;
+        if !self.tags.is_empty() {
+            form_data.append_with_str("tags", &self.tags.join(","))?;
+        }
+        form_data.append_with_str("severity", self.severity.as_str())?;
+        // The original times, instead of the time of the import
+        match self.created {
+            Some(created) => {
+                form_data.append_with_str("created_at", &created.to_rfc3339())?;
+                form_data.append_with_str("event_at", &self.timestamp.to_rfc3339())?;
+            }
+            None => form_data.append_with_str("created_at", &self.timestamp.to_rfc3339())?,
+        }
+        if let Some(author) = &self.author {
+            form_data.append_with_str("author", author)?;
+        }
+        if let Some(fields) = &self.fields {
+            form_data.append_with_str("fields", &serde_json::to_string(fields).unwrap())?;
+        }
+        for attachment in self.attachments.iter() {
+            let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(&attachment.data[..]));
+            let options = web_sys::FilePropertyBag::new();
+            options.set_type(&attachment.mime);
+            let file = web_sys::File::new_with_u8_array_sequence_and_options(
+                &parts,
+                &attachment.name,
+                &options,
+            )?;
+            form_data.append_with_blob_and_filename("file", &file, &attachment.name)?;
+        }
+        Ok(form_data)
+    }
+
+    fn summary(&self) -> String {
+        let line = self
+            .log
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("");
+        match line.char_indices().nth(80) {
+            Some((end, _)) => format!("{}…", &line[..end]),
+            None => line.to_string(),
+        }
+    }
+}
+
+impl ImportState {
+    pub fn pending(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status != ImportStatus::Done)
+            .count()
+    }
+
+    // Read the chosen files, in the chronological order of the entries
+    pub fn read(&mut self, sources: Vec<Result<SourceFile, String>>) {
+        for source in sources {
+            let result = source.and_then(|source| {
+                let lower = source.name.to_lowercase();
+                if lower.ends_with(".zip") {
+                    self.read_zip(&source)
+                } else if [".md", ".markdown", ".txt"]
+                    .iter()
+                    .any(|extension| lower.ends_with(extension))
+                {
+                    let text = String::from_utf8_lossy(&source.data);
+                    let time = Local
+                        .timestamp_millis_opt(source.last_modified as i64)
+                        .single()
+                        .unwrap_or_else(Local::now);
+                    for section in split_sections(&text) {
+                        self.entries
+                            .push(imported_entry(&source.name, section, time, &mut |_| None));
+                    }
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{}: not a markdown file nor a zip archive",
+                        source.name
+                    ))
+                }
+            });
+            if let Err(err) = result {
+                self.errors.push(err);
+            }
+        }
+        self.entries.sort_by_key(|entry| entry.timestamp);
+    }
+
+    fn read_zip(&mut self, source: &SourceFile) -> Result<(), String> {
+        let error = |err: zip::result::ZipError| format!("{}: {}", source.name, err);
+        let mut archive = ZipArchive::new(Cursor::new(&source.data[..])).map_err(error)?;
+        let mut files = BTreeMap::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).map_err(error)?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut data = vec![];
+            file.read_to_end(&mut data)
+                .map_err(|err| format!("{}: {}", source.name, err))?;
+            files.insert(file.name().to_string(), data);
+        }
+
+        let time = Local
+            .timestamp_millis_opt(source.last_modified as i64)
+            .single()
+            .unwrap_or_else(Local::now);
+        let manifest_path = files
+            .keys()
+            .filter(|path| path.rsplit('/').next() == Some("manifest.json"))
+            .min_by_key(|path| path.len())
+            .cloned();
+
+        let Some(manifest_path) = manifest_path else {
+            // Plain markdown files with the files they link to
+            for (path, data) in files.iter().filter(|(path, _)| path.ends_with(".md")) {
+                let text = String::from_utf8_lossy(data);
+                let mut resolve = |link: &str| zip_file(&files, path, link, None);
+                for section in split_sections(&text) {
+                    let name = format!("{}/{}", source.name, path);
+                    self.entries
+                        .push(imported_entry(&name, section, time, &mut resolve));
+                }
+            }
+            return Ok(());
+        };
+
+        // An archive exported from the logbook
+        let root = manifest_path.trim_end_matches("manifest.json");
+        let manifest: ArchivedManifest = serde_json::from_slice(&files[&manifest_path])
+            .map_err(|err| format!("{}: invalid manifest: {}", source.name, err))?;
+        for archived in manifest.entries {
+            let path = format!("{}{}", root, archived.file);
+            let name = format!("{}/{}", source.name, archived.file);
+            let Some(data) = files.get(&path) else {
+                self.errors.push(format!("{}: missing", name));
+                continue;
+            };
+            let mimes: BTreeMap<String, &str> = archived
+                .attachments
+                .iter()
+                .map(|attachment| {
+                    (
+                        format!("{}{}", root, attachment.file),
+                        attachment.mime.as_str(),
+                    )
+                })
+                .collect();
+            let mut resolve = |link: &str| {
+                let target = resolve_path(&path, link);
+                zip_file(&files, &path, link, mimes.get(&target).copied())
+            };
+
+            let text = String::from_utf8_lossy(data);
+            let mut section = split_sections(&text).into_iter().next().unwrap_or_default();
+            // The manifest is the reference for the metadata
+            section.timestamp = DateTime::parse_from_rfc3339(&archived.timestamp)
+                .ok()
+                .map(|timestamp| timestamp.with_timezone(&Local))
+                .or(section.timestamp);
+            section.created = archived
+                .created_at
+                .and_then(|created| DateTime::parse_from_rfc3339(&created).ok())
+                .map(|created| created.with_timezone(&Local))
+                .or(section.created);
+            section.author = archived.author.or(section.author);
+            section.severity = Some(archived.severity);
+            section.tags = archived.tags;
+            let mut entry = imported_entry(&name, section, time, &mut resolve);
+            entry.fields = archived.fields;
+            self.entries.push(entry);
+        }
+        Ok(())
+    }
+}
+
+// Split a markdown file at the headings made of a timestamp (`# 2024-05-01 12:34`)
+// A file without any is a single entry
+fn split_sections(text: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = vec![];
+    let mut current = Section::default();
+    let mut in_meta = false;
+
+    for line in text.lines() {
+        if let Some(timestamp) = line.strip_prefix("# ").and_then(parse_timestamp) {
+            if current.timestamp.is_some() || !current.body.trim().is_empty() {
+                sections.push(std::mem::take(&mut current));
+            }
+            current.timestamp = Some(timestamp);
+            in_meta = true;
+            continue;
+        }
+
+        if in_meta {
+            let meta = line
+                .trim()
+                .strip_prefix("- ")
+                .and_then(|item| item.split_once(": "));
+            match meta {
+                Some((key, value)) => match key.to_lowercase().as_str() {
+                    "author" => current.author = Some(value.trim().to_string()),
+                    "logged" => current.created = parse_timestamp(value),
+                    "severity" => {
+                        current.severity = Some(Severity::parse(&value.trim().to_lowercase()))
+                    }
+                    "tags" => current.tags = parse_tag_list(value),
+                    // Links between the entries cannot be kept
+                    "reply to" => {}
+                    _ => in_meta = false,
+                },
+                None if line.trim().is_empty() && current.body.is_empty() => continue,
+                None => in_meta = false,
+            }
+            if in_meta {
+                continue;
+            }
+        }
+
+        current.body.push_str(line);
+        current.body.push('\n');
+    }
+    if current.timestamp.is_some() || !current.body.trim().is_empty() {
+        sections.push(current);
+    }
+
+    sections
+}
+
+fn parse_timestamp(text: &str) -> Option<DateTime<Local>> {
+    let text = text.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(text) {
+        return Some(timestamp.with_timezone(&Local));
+    }
+    // As written in the archived entries
+    if let Ok(timestamp) = DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S %:z") {
+        return Some(timestamp.with_timezone(&Local));
+    }
+    [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y/%m/%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+}
+
+// Make the entry, turning the links to the files into `%N` references
+fn imported_entry(
+    source: &str,
+    section: Section,
+    default_time: DateTime<Local>,
+    resolve: &mut dyn FnMut(&str) -> Option<ImportedFile>,
+) -> ImportedEntry {
+    let mut warnings = vec![];
+    let mut attachments: Vec<(String, ImportedFile)> = vec![];
+    let mut attach = |link: &str, warnings: &mut Vec<String>| -> Option<usize> {
+        if let Some(index) = attachments.iter().position(|(path, _)| path == link) {
+            return Some(index + 1);
+        }
+        match resolve(link) {
+            Some(file) => {
+                attachments.push((link.to_string(), file));
+                Some(attachments.len())
+            }
+            None => {
+                warnings.push(format!("missing file `{}`", link));
+                None
+            }
+        }
+    };
+
+    // The list of attachments added by the archive is taken back as attachments
+    let (body, listed) = split_attachment_list(&section.body);
+    let mut log = String::new();
+    let mut rest = body.as_str();
+    while let Some(link) = find_link(rest) {
+        log.push_str(&escape_references(&rest[..link.start]));
+        let source = &rest[link.start..link.end];
+        let replacement = if is_external(&link.target) {
+            source.to_string()
+        } else if link.target.ends_with(".md") {
+            // Another entry of the archive
+            link.label.clone()
+        } else {
+            match attach(&link.target, &mut warnings) {
+                Some(n) => format!("%{}", n),
+                None => source.to_string(),
+            }
+        };
+        log.push_str(&replacement);
+        rest = &rest[link.end..];
+    }
+    log.push_str(&escape_references(rest));
+    for target in listed {
+        attach(&target, &mut warnings);
+    }
+
+    if section.timestamp.is_none() {
+        warnings.push("no timestamp, the file time is used".to_string());
+    }
+    let log = log.trim().to_string();
+    let tags = merge_tags(section.tags, parse_hashtags(&log));
+
+    ImportedEntry {
+        source: source.to_string(),
+        timestamp: section.timestamp.unwrap_or(default_time),
+        created: section.created,
+        log,
+        tags,
+        severity: section.severity.unwrap_or_default(),
+        author: section.author,
+        fields: None,
+        attachments: attachments.into_iter().map(|(_, file)| file).collect(),
+        warnings,
+        status: ImportStatus::Pending,
+    }
+}
+
+// Cut the trailing "## Attachments" list off the body
+fn split_attachment_list(body: &str) -> (String, Vec<String>) {
+    let Some(start) = body.find("\n## Attachments\n") else {
+        return (body.to_string(), vec![]);
+    };
+    let mut targets = vec![];
+    for line in body[start..].lines().skip(2).map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        match line.strip_prefix("- ").and_then(find_link) {
+            Some(link) => targets.push(link.target),
+            // Not the list made by the archive
+            None => return (body.to_string(), vec![]),
+        }
+    }
+    (body[..start].to_string(), targets)
+}
+
+struct Link {
+    start: usize,
+    end: usize,
+    label: String,
+    target: String,
+}
+
+// The next `[label](target)` or `![label](target)`
+fn find_link(text: &str) -> Option<Link> {
+    let mut from = 0;
+    while let Some(open) = text[from..].find('[').map(|i| i + from) {
+        let close = text[open..].find("](").map(|i| i + open)?;
+        let label = &text[open + 1..close];
+        let end = text[close + 2..].find(')').map(|i| i + close + 2)?;
+        if label.contains(['[', '\n']) {
+            from = open + 1;
+            continue;
+        }
+        // Without the title ("...")
+        let target = text[close + 2..end].trim();
+        let target = target.split(" \"").next().unwrap_or(target);
+        let target = target.trim_start_matches('<').trim_end_matches('>');
+        let start = if open > 0 && text[..open].ends_with('!') {
+            open - 1
+        } else {
+            open
+        };
+        return Some(Link {
+            start,
+            end: end + 1,
+            label: label.to_string(),
+            target: percent_decode(target),
+        });
+    }
+    None
+}
+
+// Back from the percent-encoded link destination, e.g. `%20` and `%E6%97%A5` of the file names
+fn percent_decode(target: &str) -> String {
+    let bytes = target.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = target
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn is_external(target: &str) -> bool {
+    target.contains("://") || target.starts_with('#') || target.starts_with("mailto:")
+}
+
+// `%` in the imported text is not an attachment reference
+fn escape_references(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '%'
+            && chars
+                .peek()
+                .is_some_and(|next| next.is_ascii_digit() || *next == '{')
+        {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+// Path of a link relative to the file it is written in
+fn resolve_path(file: &str, link: &str) -> String {
+    let mut parts: Vec<&str> = file.split('/').collect();
+    parts.pop();
+    for part in link.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn zip_file(
+    files: &BTreeMap<String, Vec<u8>>,
+    from: &str,
+    link: &str,
+    mime: Option<&str>,
+) -> Option<ImportedFile> {
+    let path = resolve_path(from, link);
+    let data = files.get(&path)?;
+    let name = path.rsplit('/').next().unwrap_or(&path);
+    // The archive prefixes the names with the attachment numbers
+    let name = match name.split_once('_') {
+        Some((number, rest)) if number.parse::<u32>().is_ok() && !rest.is_empty() => rest,
+        _ => name,
+    };
+    Some(ImportedFile {
+        name: name.to_string(),
+        mime: mime.map_or_else(|| guess_mime(name).to_string(), str::to_string),
+        data: data.clone(),
+    })
+}
+
+fn guess_mime(name: &str) -> &'static str {
+    let extension = name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("pdf") => "application/pdf",
+        Some("csv") => "text/csv",
+        Some("tsv") => "text/tab-separated-values",
+        Some("txt" | "log") => "text/plain",
+        Some("md") => "text/markdown",
+        Some("json") => "application/json",
+        Some("mp3") => "audio/mpeg",
+        Some("wav") => "audio/wav",
+        Some("mp4") => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+// Read the files chosen in the dialog
+pub async fn read_source_files(files: Vec<web_sys::File>) -> Vec<Result<SourceFile, String>> {
+    let mut sources = vec![];
+    for file in files {
+        let buffer = wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await;
+        sources.push(
+            buffer
+                .map(|buffer| SourceFile {
+                    name: file.name(),
+                    last_modified: file.last_modified(),
+                    data: js_sys::Uint8Array::new(&buffer).to_vec(),
+                })
+                .map_err(|_| format!("{}: cannot be read", file.name())),
+        );
+    }
+    sources
+}
+
+impl Model {
+    // Wizard importing markdown files or an archive
+    pub fn import_dialog(&self, ctx: &Context<Self>) -> Html {
+        let Some(state) = self.import.as_ref() else {
+            return html! {};
+        };
+
+        let onchange = ctx.link().callback(|e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            let files = input
+                .files()
+                .map(|files| (0..files.length()).filter_map(|i| files.get(i)).collect())
+                .unwrap_or_default();
+            Msg::ReadImportFiles(files)
+        });
+        let done = state.entries.len() - state.pending();
+        let attachments: usize = state
+            .entries
+            .iter()
+            .map(|entry| entry.attachments.len())
+            .sum();
+
+        html! {
+            <div class="dialog-backdrop">
+                <div class="dialog import-dialog" role="dialog" aria-modal="true" aria-label={self.t(Text::ImportEntries)}>
+                    <div class="dialog-header">
+                        <span>{ self.t(Text::ImportEntries) }</span>
+                        if !state.running {
+                            <button class="close-button" aria-label={self.t(Text::Close)} onclick={ctx.link().callback(|_| Msg::OpenImport(false))}>
+                                {"×"}
+                            </button>
+                        }
+                    </div>
+                    <input
+                        type="file"
+                        multiple=true
+                        accept=".md,.markdown,.txt,.zip"
+                        disabled={state.running}
+                        {onchange}
+                    />
+                    if state.reading {
+                        <div class="dialog-status">{ self.t(Text::ReadingFiles) }</div>
+                    }
+                    { for state.errors.iter().map(|err| html! { <div class="dialog-error" role="alert">{ err }</div> }) }
+                    if !state.entries.is_empty() {
+                        <table class="import-preview">
+                            <thead>
+                                <tr>
+                                    <th/>
+                                    <th>{ self.t(Text::Time) }</th>
+                                    <th>{ self.t(Text::Entry) }</th>
+                                    <th>{ self.t(Text::Tags) }</th>
+                                    <th>{ self.t(Text::Attachments) }</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                { for state.entries.iter().map(|entry| self.import_row(entry)) }
+                            </tbody>
+                        </table>
+                        <div class="dialog-status">
+                            {
+                                format!(
+                                    "{} {}, {} {}",
+                                    state.entries.len(),
+                                    self.t(Text::EntryCount),
+                                    attachments,
+                                    self.t(Text::AttachmentCount)
+                                )
+                            }
+                            if done > 0 {
+                                { format!(" · {}: {} / {}", self.t(Text::Imported), done, state.entries.len()) }
+                            }
+                        </div>
+                    }
+                    <div class="dialog-actions">
+                        <button
+                            disabled={state.running || state.pending() == 0}
+                            onclick={ctx.link().callback(|_| Msg::StartImport)}
+                        >
+                            { self.t(if state.running { Text::Importing } else { Text::Import }) }
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
+    fn import_row(&self, entry: &ImportedEntry) -> Html {
+        let (icon, title) = match &entry.status {
+            ImportStatus::Pending => ("schedule", self.t(Text::Pending).to_string()),
+            ImportStatus::Done => ("check_circle", self.t(Text::Imported).to_string()),
+            ImportStatus::Failed(err) => ("error", err.clone()),
+        };
+        let names: Vec<&str> = entry
+            .attachments
+            .iter()
+            .map(|file| file.name.as_str())
+            .collect();
+
+        html! {
+            <tr class={classes!(matches!(entry.status, ImportStatus::Failed(_)).then_some("failed"))}>
+                <td><i class="material-symbols-outlined" role="img" aria-label={title.clone()} title={title.clone()}>{ icon }</i></td>
+                <td class="import-time">{ entry.timestamp.format("%Y-%m-%d %H:%M").to_string() }</td>
+                <td>
+                    <div class="import-summary" title={entry.source.clone()}>{ entry.summary() }</div>
+                    { for entry.warnings.iter().map(|warning| html! { <div class="import-warning">{ warning }</div> }) }
+                    if let ImportStatus::Failed(err) = &entry.status {
+                        <div class="dialog-error" role="alert">{ err }</div>
+                    }
+                </td>
+                <td>{ entry.tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" ") }</td>
+                <td title={names.join("\n")}>{ entry.attachments.len() }</td>
+            </tr>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::link_path;
+
+    #[test]
+    fn sections_at_timestamp_headings() {
+        let sections = split_sections(
+            "Notes before the first heading\n\
+             # 2024-05-01 12:34:56 +09:00\n\
+             - Author: ops\n\
+             - Severity: Warning\n\
+             - Tags: rf, vacuum\n\
+             \n\
+             RF trip\n\
+             - not metadata: kept\n\
+             # Not a timestamp\n\
+             # 2024-05-01T13:00:00+09:00\n\
+             Recovered\n",
+        );
+        assert_eq!(sections.len(), 3);
+        assert!(sections[0].timestamp.is_none());
+        assert_eq!(sections[0].body, "Notes before the first heading\n");
+
+        let first = &sections[1];
+        assert_eq!(
+            first.timestamp,
+            parse_timestamp("2024-05-01T12:34:56+09:00")
+        );
+        assert_eq!(first.author.as_deref(), Some("ops"));
+        assert_eq!(first.severity, Some(Severity::Warning));
+        assert_eq!(first.tags, ["rf", "vacuum"]);
+        assert_eq!(
+            first.body,
+            "RF trip\n- not metadata: kept\n# Not a timestamp\n"
+        );
+        assert_eq!(sections[2].body, "Recovered\n");
+    }
+
+    #[test]
+    fn links_in_text() {
+        let link = find_link("see ![plot](<a%20b.png> \"title\") and [x](y)").unwrap();
+        assert_eq!((link.start, link.end), (4, 32));
+        assert_eq!(link.label, "plot");
+        assert_eq!(link.target, "a b.png");
+
+        // Not a label across lines, nor a malformed `%`
+        let link = find_link("[a\nb](c) [50%](d%zz%2)").unwrap();
+        assert_eq!(link.label, "50%");
+        assert_eq!(link.target, "d%zz%2");
+        assert!(find_link("[unclosed](link").is_none());
+    }
+
+    #[test]
+    fn paths_relative_to_the_file() {
+        assert_eq!(
+            resolve_path("log/entries/a.md", "../files/b.png"),
+            "log/files/b.png"
+        );
+        assert_eq!(resolve_path("a.md", "./b.png"), "b.png");
+        assert_eq!(resolve_path("a/b/c.md", "../../d/e.txt"), "d/e.txt");
+    }
+
+    #[test]
+    fn archived_links_read_back() {
+        let path = "attachments/entry_1/2_beam loss (1) 日本語#%.png";
+        let markdown = format!("![beam]({})", link_path(&format!("../{}", path)));
+        let link = find_link(&markdown).unwrap();
+        assert_eq!(
+            resolve_path("logbook/entries/2024-05-01_123456_1.md", &link.target),
+            format!("logbook/{}", path)
+        );
+    }
+}
@@ -0,0 +1,471 @@
+use chrono::{Datelike, NaiveDate};
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlElement, HtmlInputElement, KeyboardEvent};
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::Msg;
+
+// What can be done from the keyboard, also listed in the command palette
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    NextEntry,
+    PreviousEntry,
+    LatestEntry,
+    GoToDate,
+    FocusSearch,
+    Reply,
+    NewEntry,
+    OpenAttachments,
+    ToggleThread,
+    ClearFilters,
+    Export,
+    Import,
+    Display,
+    Notifications,
+    OpenPalette,
+    Help,
+    // Closes the palette, the help or a dialog, or else clears the selection
+    Close,
+}
+
+impl Command {
+    pub const ALL: [Command; 17] = [
+        Command::NextEntry,
+        Command::PreviousEntry,
+        Command::LatestEntry,
+        Command::GoToDate,
+        Command::FocusSearch,
+        Command::Reply,
+        Command::NewEntry,
+        Command::OpenAttachments,
+        Command::ToggleThread,
+        Command::ClearFilters,
+        Command::Export,
+        Command::Import,
+        Command::Display,
+        Command::Notifications,
+        Command::OpenPalette,
+        Command::Help,
+        Command::Close,
+    ];
+
+    // Key as shown to the user
+    pub fn binding(&self) -> Option<&'static str> {
+        match self {
+            Command::NextEntry => Some("j"),
+            Command::PreviousEntry => Some("k"),
+            Command::LatestEntry => Some("G"),
+            Command::GoToDate => Some("g"),
+            Command::FocusSearch => Some("/"),
+            Command::Reply => Some("r"),
+            Command::NewEntry => Some("n"),
+            Command::OpenAttachments => Some("o"),
+            Command::ToggleThread => Some("t"),
+            Command::OpenPalette => Some("Ctrl+K"),
+            Command::Help => Some("?"),
+            Command::Close => Some("Esc"),
+            Command::ClearFilters
+            | Command::Export
+            | Command::Import
+            | Command::Display
+            | Command::Notifications => None,
+        }
+    }
+
+    // The command bound to the key pressed
+    // Only Ctrl+K is taken while typing, e.g. in the editor or the search box
+    pub fn from_event(event: &KeyboardEvent) -> Option<Command> {
+        // Already handled, e.g. by the lightbox, which keeps the keys while open
+        if event.default_prevented() || lightbox_open() {
+            return None;
+        }
+        let key = event.key();
+        if (event.ctrl_key() || event.meta_key()) && key.eq_ignore_ascii_case("k") {
+            return Some(Command::OpenPalette);
+        }
+        if event.ctrl_key() || event.meta_key() || event.alt_key() || is_typing(event) {
+            return None;
+        }
+        Command::ALL
+            .into_iter()
+            .find(|command| match command.binding() {
+                Some("Esc") => key == "Escape",
+                Some(binding) => key == binding,
+                None => false,
+            })
+    }
+}
+
+fn lightbox_open() -> bool {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.query_selector(".lightbox.open").ok().flatten())
+        .is_some()
+}
+
+fn is_typing(event: &KeyboardEvent) -> bool {
+    event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        .and_then(|element| {
+            element
+                .closest("input, textarea, select, [contenteditable], .CodeMirror")
+                .ok()
+        })
+        .flatten()
+        .is_some()
+}
+
+// Listen the keys on the whole page
+// Taken in the capture phase, so that Ctrl+K goes to the palette rather than the editor
+pub fn register_keyboard(link: yew::html::Scope<Model>) {
+    let on_keydown = Closure::<dyn Fn(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+        if let Some(command) = Command::from_event(&event) {
+            event.prevent_default();
+            if command == Command::OpenPalette {
+                event.stop_propagation();
+            }
+            link.send_message(Msg::RunCommand(command));
+        }
+    });
+    if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+        let _ = document.add_event_listener_with_callback_and_bool(
+            "keydown",
+            on_keydown.as_ref().unchecked_ref(),
+            true,
+        );
+    }
+    on_keydown.forget();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaletteMode {
+    Commands,
+    // Typing a date to jump to
+    Date,
+}
+
+// State of the command palette, while open
+#[derive(Debug, Clone)]
+pub struct Palette {
+    pub mode: PaletteMode,
+    pub query: String,
+    // Highlighted item among the matching commands
+    pub index: usize,
+    pub error: Option<String>,
+}
+
+impl Palette {
+    pub fn new(mode: PaletteMode) -> Self {
+        Self {
+            mode,
+            query: String::new(),
+            index: 0,
+            error: None,
+        }
+    }
+
+    // The commands containing all the words typed, in the language shown
+    pub fn commands(&self, locale: Locale) -> Vec<Command> {
+        let query = self.query.to_lowercase();
+        Command::ALL
+            .into_iter()
+            .filter(|command| *command != Command::OpenPalette)
+            .filter(|command| {
+                let label = locale.text(Text::CommandName(*command)).to_lowercase();
+                query.split_whitespace().all(|word| label.contains(word))
+            })
+            .collect()
+    }
+}
+
+// A date typed as YYYY-MM-DD, or MM-DD in the current year
+pub fn parse_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text = text.trim().replace('/', "-");
+    NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(&format!("{}-{}", today.year(), text), "%Y-%m-%d").ok()
+        })
+}
+
+impl Model {
+    // The selected entry, if still shown
+    pub fn selected_entry(&self) -> Option<&Entry> {
+        self.selected
+            .as_ref()
+            .and_then(|id| self.find_entry(id))
+            .filter(|entry| self.is_shown(entry))
+    }
+
+    // Select the entry `delta` rows away, starting from the viewport if none is selected
+    pub fn move_selection(&mut self, delta: isize) {
+        let rows = self.layout();
+        if rows.is_empty() {
+            return;
+        }
+        let current = self
+            .selected
+            .as_ref()
+            .and_then(|id| rows.iter().position(|row| &row.entry.id == id));
+        let index = match current {
+            Some(index) => index.saturating_add_signed(delta).min(rows.len() - 1),
+            // The last row in view
+            None => rows
+                .partition_point(|row| row.top < self.viewport.scroll_top + self.viewport.height)
+                .saturating_sub(1),
+        };
+        let (id, top, bottom) = {
+            let row = &rows[index];
+            (row.entry.id.clone(), row.top, row.bottom())
+        };
+        self.selected = Some(id);
+        self.reveal(top, bottom);
+    }
+
+    // Scroll as little as possible to bring the span into view
+    pub fn reveal(&mut self, top: f64, bottom: f64) {
+        let Some(content) = self.content_ref.cast::<HtmlElement>() else {
+            return;
+        };
+        let scroll_top = content.scroll_top() as f64;
+        let height = content.client_height() as f64;
+        if top < scroll_top {
+            content.set_scroll_top(top as i32);
+        } else if bottom > scroll_top + height {
+            content.set_scroll_top((bottom - height).min(top) as i32);
+        }
+        self.update_viewport();
+    }
+
+    // The first shown entry of the day, or the nearest one
+    pub fn entry_on_date(&self, date: NaiveDate) -> Result<String, String> {
+        let rows = self.layout();
+        let Some(first) = rows.first() else {
+            return Err(self.t(Text::NoEntriesShown).to_string());
+        };
+        if date < first.date && !self.exhausted {
+            return Err(format!(
+                "{} ({} {})",
+                self.t(Text::OlderNotLoaded),
+                self.t(Text::LoadedBackTo),
+                self.locale.date(first.date)
+            ));
+        }
+        rows.iter()
+            .find(|row| row.date >= date)
+            .or(rows.last())
+            .map(|row| row.entry.id.clone())
+            .ok_or_else(|| self.t(Text::NoEntriesShown).to_string())
+    }
+
+    // Open the attachment previews of the entry, or close them if all are open
+    pub fn toggle_attachments(&mut self, id: &str) {
+        let Some(entry) = self.find_entry(id) else {
+            return;
+        };
+        let tokens: Vec<String> = entry
+            .attachments
+            .iter()
+            .map(|attachment| attachment.download_token.clone())
+            .collect();
+        if tokens
+            .iter()
+            .all(|token| self.opened_attachments.contains(token))
+        {
+            for token in tokens.iter() {
+                self.opened_attachments.remove(token);
+            }
+        } else {
+            self.opened_attachments.extend(tokens);
+        }
+    }
+
+    pub fn focus_search(&self) {
+        if let Some(input) = self.search_ref.cast::<HtmlInputElement>() {
+            let _ = input.focus();
+            input.select();
+        }
+    }
+
+    // Keep the palette input focused while it is open
+    pub fn focus_palette(&self) {
+        let Some(input) = self.palette_ref.cast::<HtmlInputElement>() else {
+            return;
+        };
+        let active = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.active_element());
+        if active.as_ref() != Some(input.as_ref()) {
+            let _ = input.focus();
+        }
+    }
+
+    // Move the focus along with the selection, for the screen readers
+    // Only from another entry or nowhere, not to take it from the controls
+    pub fn focus_selected(&self) {
+        let Some(id) = &self.selected else {
+            return;
+        };
+        let document = web_sys::window().and_then(|window| window.document());
+        let Some(element) = document
+            .as_ref()
+            .and_then(|document| document.get_element_by_id(&format!("entry-{}", id)))
+            .and_then(|element| element.dyn_into::<HtmlElement>().ok())
+        else {
+            return;
+        };
+        let active = document.and_then(|document| document.active_element());
+        let on_entry = match &active {
+            Some(active) => {
+                active.tag_name() == "BODY" || active.class_list().contains("entry-item")
+            }
+            None => true,
+        };
+        if on_entry && active.as_ref() != Some(element.as_ref()) {
+            let _ = element.focus();
+        }
+    }
+
+    pub fn command_palette(&self, ctx: &Context<Self>) -> Html {
+        let Some(palette) = &self.palette else {
+            return html! {};
+        };
+        let commands = match palette.mode {
+            PaletteMode::Commands => palette.commands(self.locale),
+            PaletteMode::Date => vec![],
+        };
+        let today = self.zone.today();
+
+        let oninput = ctx.link().callback(|e: InputEvent| {
+            Msg::UpdatePalette(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let link = ctx.link().clone();
+        let onkeydown = Callback::from(move |e: KeyboardEvent| {
+            let message = match e.key().as_str() {
+                "ArrowDown" => Msg::MovePalette(1),
+                "ArrowUp" => Msg::MovePalette(-1),
+                "Enter" => Msg::SubmitPalette,
+                "Escape" => Msg::RunCommand(Command::Close),
+                _ => return,
+            };
+            e.prevent_default();
+            link.send_message(message);
+        });
+        let placeholder = match palette.mode {
+            PaletteMode::Commands => self.t(Text::TypeCommand),
+            PaletteMode::Date => self.t(Text::DateFormat),
+        };
+
+        html! {
+            <div class="dialog-backdrop" onclick={ctx.link().callback(|_| Msg::RunCommand(Command::Close))}>
+                <div class="dialog command-palette" role="dialog" aria-modal="true" aria-label={self.t(Text::CommandName(Command::OpenPalette))} onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}>
+                    <input
+                        ref={self.palette_ref.clone()}
+                        class="palette-input"
+                        role="combobox"
+                        aria-expanded="true"
+                        aria-controls="palette-list"
+                        aria-activedescendant={format!("palette-item-{}", palette.index)}
+                        aria-label={placeholder}
+                        placeholder={placeholder}
+                        value={palette.query.clone()}
+                        {oninput}
+                        {onkeydown}
+                    />
+                    if let Some(error) = &palette.error {
+                        <div class="dialog-error" role="alert">{ error }</div>
+                    }
+                    if palette.mode == PaletteMode::Date {
+                        <div class="palette-hint">
+                            {
+                                match parse_date(&palette.query, today) {
+                                    Some(date) => format!("{} {} ({})", self.t(Text::GoTo), self.locale.date(date), self.zone.label(self.locale)),
+                                    None => self.t(Text::EnterDate).to_string(),
+                                }
+                            }
+                        </div>
+                    }
+                    <ul id="palette-list" class="palette-list" role="listbox">
+                        {
+                            for commands.iter().enumerate().map(|(i, command)| {
+                                let command = *command;
+                                let onclick = ctx.link().callback(move |_| Msg::RunCommand(command));
+                                html! {
+                                    <li
+                                        id={format!("palette-item-{}", i)}
+                                        class={classes!("palette-item", (i == palette.index).then_some("active"))}
+                                        role="option"
+                                        aria-selected={(i == palette.index).to_string()}
+                                        {onclick}
+                                    >
+                                        <span>{ self.t(Text::CommandName(command)) }</span>
+                                        if let Some(binding) = command.binding() {
+                                            <kbd>{ binding }</kbd>
+                                        }
+                                    </li>
+                                }
+                            })
+                        }
+                    </ul>
+                </div>
+            </div>
+        }
+    }
+
+    // Overlay listing the key bindings
+    pub fn help_overlay(&self, ctx: &Context<Self>) -> Html {
+        if !self.help {
+            return html! {};
+        }
+        let close = ctx.link().callback(|_| Msg::RunCommand(Command::Close));
+        html! {
+            <div class="dialog-backdrop" onclick={close.clone()}>
+                <div class="dialog help-overlay" role="dialog" aria-modal="true" aria-label={self.t(Text::CommandName(Command::Help))} onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}>
+                    <div class="dialog-header">
+                        <span>{ self.t(Text::CommandName(Command::Help)) }</span>
+                        <button class="close-button" aria-label={self.t(Text::Close)} onclick={close}>{"×"}</button>
+                    </div>
+                    <table class="help-table">
+                        <tr>
+                            <td><kbd>{"Ctrl+Enter"}</kbd></td>
+                            <td>{ self.t(Text::SubmitEntry) }</td>
+                        </tr>
+                        {
+                            for Command::ALL.into_iter().filter_map(|command| {
+                                command.binding().map(|binding| html! {
+                                    <tr>
+                                        <td><kbd>{ binding }</kbd></td>
+                                        <td>{ self.t(Text::CommandName(command)) }</td>
+                                    </tr>
+                                })
+                            })
+                        }
+                    </table>
+                </div>
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_typed_in_the_palette() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        assert_eq!(parse_date("2023-12-31", today), date(2023, 12, 31));
+        assert_eq!(parse_date(" 2023/1/2 ", today), date(2023, 1, 2));
+        // The year of today when left out
+        assert_eq!(parse_date("02-29", today), date(2024, 2, 29));
+        assert_eq!(parse_date("7/4", today), date(2024, 7, 4));
+        assert_eq!(parse_date("2023-02-29", today), None);
+        assert_eq!(parse_date("13-01", today), None);
+        assert_eq!(parse_date("yesterday", today), None);
+        assert_eq!(parse_date("", today), None);
+    }
+}
@@ -1,18 +1,94 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
 use anyhow::Error;
-use chrono::Local;
 use gloo_net::http::Request;
 use gloo_timers::callback::{Interval, Timeout};
-use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+use wasm_bindgen::{prelude::*, JsCast, JsValue};
 use wasm_bindgen_futures::spawn_local;
-use web_sys::{FormData, HtmlElement};
+use web_sys::{
+    FormData, HtmlElement, HtmlInputElement, HtmlSelectElement, Notification,
+    NotificationPermission,
+};
 use yew::prelude::*;
 
+mod api;
+use api::*;
+mod archive;
+mod export;
+use export::*;
+mod i18n;
+use i18n::*;
+mod import;
+use import::*;
+mod keyboard;
+use keyboard::*;
 mod models;
 use models::*;
+mod notifications;
+use notifications::*;
 
+mod plot;
+mod preferences;
+use preferences::*;
+mod preview;
+mod references;
 mod render;
+mod search;
+mod severity;
+use severity::*;
+mod tags;
+use tags::*;
+mod templates;
+use templates::*;
+mod threads;
+mod timezone;
+use timezone::*;
+mod virtual_list;
+use virtual_list::*;
 
 impl Model {
+    // Whether the entry passes the filters
+    fn is_shown(&self, entry: &Entry) -> bool {
+        self.tag_filter.matches(entry)
+            && self.severity_filter.matches(entry)
+            && self.matches_search(entry)
+    }
+
+    // Name of the entry in the timeline, for the screen readers
+    fn entry_label(&self, entry: &Entry, date: chrono::NaiveDate) -> String {
+        let time = self.zone.format(&entry.timestamp, "%H:%M");
+        match &entry.author {
+            Some(author) => format!("{} {}, {}", self.locale.date(date), time, author),
+            None => format!("{} {}", self.locale.date(date), time),
+        }
+    }
+
+    // Insert the entry by its event time, returning where it went
+    // Entries come by the submission time, so a backdated one goes among the older ones
+    fn insert_entry(&mut self, entry: Entry) -> usize {
+        let index = self
+            .entries
+            .partition_point(|other| other.timestamp <= entry.timestamp);
+        self.entries.insert(index, entry);
+        index
+    }
+
+    // Banner when the entry linked to is not in the logbook
+    fn missing_entry_banner(&self, ctx: &Context<Self>) -> Html {
+        let Some(id) = &self.missing_entry else {
+            return html! {};
+        };
+        html! {
+            <div class="api-banner" role="status">
+                <span>{ format!("{}: {}", self.t(Text::EntryNotFound), id) }</span>
+                <button class="close-button" aria-label={self.t(Text::Close)} onclick={ctx.link().callback(|_| Msg::DismissMissingEntry)}>
+                    {"×"}
+                </button>
+            </div>
+        }
+    }
+
     // Control the scroll bar position
     fn scroll_to_position(&self, offset: i32, from_bottom: bool, waiting_time: u32) {
         let content_ref = self.content_ref.clone();
@@ -36,8 +112,61 @@ pub enum Msg {
     GetEntries(i64, i64),
     LoadMoreEntries,
     ReceiveResponse(Result<Vec<Entry>, Error>),
-    ReceiveLatestEntry(Entry),
+    ReceiveLatestEntry(Box<Entry>),
+    EntryPosted,
+    EntryFailed(String),
     ExtendDownloadLifetime,
+    ReceiveApiCheck(Option<ApiWarning>),
+    DismissApiWarning,
+    DismissMissingEntry,
+    ToggleAttachmentPreview(String),
+    ToggleTagFilter(String),
+    SetTagMatch(TagMatch),
+    ClearTagFilter,
+    UpdateDraftTags(String),
+    UpdateDraftSeverity(Severity),
+    UpdateDraftEventTime(String),
+    ToggleSeverityFilter(Severity),
+    AcknowledgeCritical(Option<String>),
+    SetDisplayZone(DisplayZone),
+    SetLocale(Locale),
+    ShowEntry(String),
+    ReceiveTemplates(Vec<Template>),
+    SelectTemplate(Option<String>),
+    UpdateTemplateField(String, String),
+    ReplyTo(Option<String>),
+    ToggleThread(String),
+    Scrolled,
+    LayoutChanged,
+    OpenExport(bool),
+    UpdateExport(ExportField, String),
+    Export(ReportFormat),
+    ReceiveExportEntries(ReportFormat, Result<Vec<Entry>, Error>),
+    ExportFinished(Result<(), String>),
+    OpenImport(bool),
+    ReadImportFiles(Vec<web_sys::File>),
+    ReceiveImportFiles(Vec<Result<SourceFile, String>>),
+    StartImport,
+    ImportProgress(usize, Result<(), String>),
+    ImportFinished,
+    RunCommand(Command),
+    UpdatePalette(String),
+    MovePalette(isize),
+    SubmitPalette,
+    UpdateSearch(String),
+    OpenPreferences(bool),
+    UpdatePreferences(PreferenceField, String),
+    PollEntries,
+    ReceiveLiveEntries(Vec<Entry>),
+    MarkRead,
+    OpenWatchRules(bool),
+    SetWatchKind(RuleKind),
+    UpdateWatchValue(String),
+    AddWatchRule,
+    MuteWatchRule(usize),
+    RemoveWatchRule(usize),
+    RequestNotifications,
+    NotificationPermission,
 }
 
 impl Component for Model {
@@ -63,8 +192,39 @@ impl Component for Model {
         let link = ctx.link().clone();
         link.send_message(Msg::GetEntries(DEFAULT_LIMIT, 0));
 
+        // Backend API version
+        let api_link = link.clone();
+        spawn_local(async move {
+            api_link.send_message(Msg::ReceiveApiCheck(check_api_version().await));
+        });
+
+        // Entry templates
+        let templates_link = link.clone();
+        spawn_local(async move {
+            match Request::get("templates.json").send().await {
+                Ok(response) => match response.json::<Vec<Template>>().await {
+                    Ok(templates) => templates_link.send_message(Msg::ReceiveTemplates(templates)),
+                    Err(err) => web_sys::console::log_1(
+                        &format!("Invalid templates.json: {:?}", err).into(),
+                    ),
+                },
+                Err(err) => web_sys::console::log_1(
+                    &format!("Failed to load templates.json: {:?}", err).into(),
+                ),
+            }
+        });
+
         // Register a call back to JavaScript
         register_entry_callback(ctx.link().clone());
+        register_keyboard(ctx.link().clone());
+        register_attention(ctx.link().clone());
+
+        // Display preferences, already applied by index.html but set again as loaded
+        let preferences = Preferences::load();
+        preferences.apply();
+        let locale = load_locale();
+        locale.apply();
+        plot::register_plot_callbacks();
 
         // Trigger extend download lifetime every 2 mins
         let callback = link.callback(|_| Msg::ExtendDownloadLifetime);
@@ -72,6 +232,12 @@ impl Component for Model {
             callback.emit(());
         });
 
+        // Look for the entries logged by others
+        let callback = link.callback(|_| Msg::PollEntries);
+        let poll_interval = Interval::new(POLL_INTERVAL, move || {
+            callback.emit(());
+        });
+
         // Make the instance
         Self {
             client_hash: hash,
@@ -79,8 +245,44 @@ impl Component for Model {
             limit: DEFAULT_LIMIT,
             offset: 0,
             loading: false,
+            exhausted: false,
             content_ref: NodeRef::default(),
+            sentinel_ref: NodeRef::default(),
+            sentinel_observer: None,
+            viewport: Viewport::default(),
+            rendered_range: 0..0,
+            row_heights: RowHeights::default(),
+            scroll_anchor: None,
+            highlight: None,
+            rendered: RefCell::new(HashMap::new()),
+            opened_attachments: HashSet::new(),
+            expanded_threads: HashSet::new(),
+            tag_filter: TagFilter::default(),
+            severity_filter: SeverityFilter::default(),
+            acknowledged: load_acknowledged(),
+            zone: load_zone(),
+            locale,
+            announcement: String::new(),
+            api_warning: None,
+            missing_entry: None,
+            selected: None,
+            palette: None,
+            palette_ref: NodeRef::default(),
+            help: false,
+            search: String::new(),
+            search_ref: NodeRef::default(),
+            preferences,
+            preferences_open: false,
+            watch_rules: load_rules(),
+            watch_draft: None,
+            unread: 0,
+            draft: Draft::default(),
+            templates: vec![],
+            export: None,
+            import: None,
+            pending_entry: None,
             interval: Some(interval),
+            poll_interval: Some(poll_interval),
         }
     }
 
@@ -94,6 +296,37 @@ impl Component for Model {
             // ---------------------------------------------------------------------------
             Msg::AddEntry(content, attachments) => {
                 let link = ctx.link().clone();
+                self.draft.submit_error = None;
+                // Event time, when logged after the fact
+                let event_at = match self.draft_event_time() {
+                    Ok(event_at) => event_at,
+                    Err(err) => {
+                        self.draft.event_error = Some(err);
+                        return true;
+                    }
+                };
+                // Templated entry: the form comes first, then the free text
+                let mut content = content;
+                let mut fields = None;
+                match self
+                    .selected_template()
+                    .map(|t| t.compile(&self.draft.fields, self.locale))
+                {
+                    Some(Ok((markdown, values))) => {
+                        if !content.trim().is_empty() {
+                            content = format!("{}\n{}", markdown, content);
+                        } else {
+                            content = markdown;
+                        }
+                        fields = Some(values);
+                    }
+                    Some(Err(errors)) => {
+                        // Keep the editor as it is to fix the inputs
+                        self.draft.errors = errors;
+                        return true;
+                    }
+                    None => {}
+                }
                 if content.is_empty() && attachments.is_empty() {
                     link.send_message(Msg::ReceiveResponse(Err(anyhow::anyhow!("Empty entry"))));
                     return false;
@@ -102,6 +335,31 @@ impl Component for Model {
                 let form_data = FormData::new().unwrap();
                 // Content
                 form_data.append_with_str("content", &content).unwrap();
+                // Explicit tags
+                let tags = parse_tag_list(&self.draft.tags);
+                if !tags.is_empty() {
+                    form_data.append_with_str("tags", &tags.join(",")).unwrap();
+                }
+                // Severity
+                form_data
+                    .append_with_str("severity", self.draft.severity.as_str())
+                    .unwrap();
+                // Parent of a reply
+                if let Some(parent) = &self.draft.reply_to {
+                    form_data.append_with_str("parent", parent).unwrap();
+                }
+                // Event time
+                if let Some(event_at) = event_at {
+                    form_data
+                        .append_with_str("event_at", &event_at.to_rfc3339())
+                        .unwrap();
+                }
+                // Template field values
+                if let Some(fields) = fields {
+                    form_data
+                        .append_with_str("fields", &serde_json::to_string(&fields).unwrap())
+                        .unwrap();
+                }
                 // Attachments
                 for file in attachments {
                     form_data
@@ -110,79 +368,55 @@ impl Component for Model {
                 }
 
                 spawn_local(async move {
-                    let request_init = web_sys::RequestInit::new();
-                    request_init.set_method("POST");
-                    request_init.set_body(&JsValue::from(form_data));
-
-                    let request = web_sys::Request::new_with_str_and_init(
-                        "http://127.0.0.1:8080/add_entry",
-                        &request_init,
-                    )
-                    .unwrap();
-
-                    let window = web_sys::window().unwrap();
-                    let fetch_promise = window.fetch_with_request(&request);
-
-                    match wasm_bindgen_futures::JsFuture::from(fetch_promise).await {
-                        Ok(response) => {
-                            let response: web_sys::Response = response.dyn_into().unwrap();
-                            if response.ok() {
-                                link.send_message(Msg::GetEntries(1, 0));
-                            } else {
-                                link.send_message(Msg::ReceiveResponse(Err(anyhow::anyhow!(
-                                    "Request failed."
-                                ))));
-                            }
-                        }
-                        Err(err) => {
-                            link.send_message(Msg::ReceiveResponse(Err(anyhow::anyhow!(format!(
-                                "Request failed: {:?}",
-                                err
-                            )))));
-                        }
+                    match post_entry(form_data).await {
+                        Ok(()) => link.send_message(Msg::EntryPosted),
+                        // The draft stays to be sent again
+                        Err(err) => link.send_message(Msg::EntryFailed(err)),
                     }
                 });
                 true
             }
 
+            // ---------------------------------------------------------------------------
+            // Message: EntryPosted
+            // ---------------------------------------------------------------------------
+            Msg::EntryPosted => {
+                // Accepted by the backend, so the draft is done with
+                self.draft = Draft::default();
+                clear_editor();
+                ctx.link().send_message(Msg::GetEntries(1, 0));
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: EntryFailed
+            // ---------------------------------------------------------------------------
+            Msg::EntryFailed(error) => {
+                web_sys::console::log_1(&format!("Error! {}", error).into());
+                self.draft.submit_error = Some(error);
+                true
+            }
+
             // ---------------------------------------------------------------------------
             // Message: GetEntries
             // ---------------------------------------------------------------------------
             Msg::GetEntries(limit, offset) => {
                 let link = ctx.link().clone();
                 self.loading = true;
-                let request = format!(
-                    "http://127.0.0.1:8080/get_entries?client={}&limit={}&offset={}",
-                    self.client_hash, limit, offset
-                );
+                let client_hash = self.client_hash.clone();
                 spawn_local(async move {
-                    if let Ok(response) = Request::get(&request).send().await {
-                        if let Ok(json) = response.json::<Vec<EntryResponse>>().await {
-                            let entries: Vec<Entry> = json
-                                .into_iter()
-                                .filter_map(|entry_response| entry_response.to_entry())
-                                .collect();
-
-                            // Only taking the newly entered entry
-                            if limit == 1 && offset == 0 {
-                                if let Some(new_entry) = entries.into_iter().next() {
-                                    link.send_message(Msg::ReceiveLatestEntry(new_entry));
-                                }
-                            } else {
-                                link.send_message(Msg::ReceiveResponse(Ok(entries)));
+                    match fetch_page(&client_hash, limit, offset).await {
+                        // Only taking the newly entered entry
+                        Ok(entries) if limit == 1 && offset == 0 => {
+                            if let Some(new_entry) = entries.into_iter().next() {
+                                link.send_message(Msg::ReceiveLatestEntry(Box::new(new_entry)));
                             }
-                        } else {
-                            link.send_message(Msg::ReceiveResponse(Err(anyhow::anyhow!(
-                                "Failed to parse request as text"
-                            ))));
                         }
-                    } else {
-                        link.send_message(Msg::ReceiveResponse(Err(anyhow::anyhow!(
-                            "Request failed."
-                        ))));
+                        result => link.send_message(Msg::ReceiveResponse(result)),
                     }
                 });
-                false
+                // Shows the loading indicator, except for the latest entry
+                !(limit == 1 && offset == 0)
             }
 
             // ---------------------------------------------------------------------------
@@ -190,12 +424,26 @@ impl Component for Model {
             // ---------------------------------------------------------------------------
             Msg::ReceiveLatestEntry(new_entry) => {
                 // For the new input entry
-                self.entries.push(new_entry);
-                self.offset += 1;
+                let id = new_entry.id.clone();
+                self.announcement =
+                    format!("{}: {}", self.t(Text::EntryAdded), new_entry.summary(80));
+                // Already there when the polling came first
+                if self.find_entry(&id).is_none() {
+                    self.insert_entry(*new_entry);
+                    self.offset += 1;
+                }
+                let latest = self.entries.last().is_some_and(|entry| entry.id == id);
                 self.loading = false;
 
-                // Force to scroll down
-                self.scroll_to_position(0, true, 50);
+                if latest {
+                    // Force to scroll down
+                    self.scroll_to_position(0, true, 50);
+                } else {
+                    // Backdated, so placed among the older entries
+                    ctx.link().send_message(Msg::ShowEntry(id));
+                }
+                // Ready for the next one
+                focus_editor();
                 true
             }
 
@@ -203,7 +451,7 @@ impl Component for Model {
             // Message: LoadMoreEntries
             // ---------------------------------------------------------------------------
             Msg::LoadMoreEntries => {
-                if !self.loading {
+                if !self.loading && !self.exhausted {
                     ctx.link()
                         .send_message(Msg::GetEntries(self.limit, self.offset));
                 }
@@ -216,24 +464,43 @@ impl Component for Model {
             Msg::ReceiveResponse(response) => {
                 match response {
                     Ok(entries) => {
-                        let mut ret = false; // in case no entries loaded newly
-
+                        self.exhausted = (entries.len() as i64) < self.limit;
                         if !entries.is_empty() {
-                            // Add the loaded entried
+                            // Add the loaded entried, keeping the top-most entry in place
+                            self.capture_scroll_anchor();
                             self.offset += entries.len() as i64;
-                            entries
-                                .into_iter()
-                                .for_each(|entry| self.entries.insert(0, entry));
-                            ret = true;
+                            // Skipping the ones already loaded, when others logged in between
+                            entries.into_iter().for_each(|entry| {
+                                if self.find_entry(&entry.id).is_none() {
+                                    self.insert_entry(entry);
+                                }
+                            });
+                            self.anchor_viewport();
                         }
                         self.loading = false;
-                        self.scroll_to_position(10, false, 50);
 
-                        ret
+                        // The entry requested by the URL hash
+                        if let Some(id) = self.pending_entry.take() {
+                            if self.find_entry(&id).is_some() {
+                                ctx.link().send_message(Msg::ShowEntry(id));
+                            } else if self.exhausted {
+                                // Paged back to the beginning without finding it
+                                self.missing_entry = Some(id);
+                            } else {
+                                // Further back in the logbook
+                                self.pending_entry = Some(id);
+                                ctx.link().send_message(Msg::LoadMoreEntries);
+                            }
+                        }
+
+                        // The sentinel may still be in view
+                        self.observe_sentinel();
+                        true
                     }
                     Err(err) => {
                         web_sys::console::log_1(&format!("Error! {:?}", err).into());
-                        false
+                        self.loading = false;
+                        true
                     }
                 }
             }
@@ -249,6 +516,742 @@ impl Component for Model {
                 });
                 false
             }
+
+            // ---------------------------------------------------------------------------
+            // Message: ReceiveApiCheck / DismissApiWarning
+            // ---------------------------------------------------------------------------
+            Msg::ReceiveApiCheck(warning) => {
+                if let Some(warning) = &warning {
+                    web_sys::console::warn_1(&warning.message(Locale::English).into());
+                }
+                self.api_warning = warning;
+                true
+            }
+            Msg::DismissApiWarning => {
+                self.api_warning = None;
+                true
+            }
+            Msg::DismissMissingEntry => {
+                self.missing_entry = None;
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ToggleAttachmentPreview
+            // ---------------------------------------------------------------------------
+            Msg::ToggleAttachmentPreview(token) => {
+                if !self.opened_attachments.remove(&token) {
+                    self.opened_attachments.insert(token);
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ToggleTagFilter / SetTagMatch / ClearTagFilter
+            // ---------------------------------------------------------------------------
+            Msg::ToggleTagFilter(tag) => {
+                self.tag_filter.toggle(tag);
+                true
+            }
+            Msg::SetTagMatch(mode) => {
+                self.tag_filter.mode = mode;
+                true
+            }
+            Msg::ClearTagFilter => {
+                self.tag_filter.tags.clear();
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: UpdateDraftTags
+            // ---------------------------------------------------------------------------
+            Msg::UpdateDraftTags(tags) => {
+                self.draft.tags = tags;
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: UpdateDraftSeverity
+            // ---------------------------------------------------------------------------
+            Msg::UpdateDraftSeverity(severity) => {
+                self.draft.severity = severity;
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ToggleSeverityFilter
+            // ---------------------------------------------------------------------------
+            Msg::ToggleSeverityFilter(severity) => {
+                self.severity_filter.toggle(severity);
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: UpdateDraftEventTime
+            // ---------------------------------------------------------------------------
+            Msg::UpdateDraftEventTime(time) => {
+                self.draft.event_time = time;
+                self.draft.event_error = None;
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: AcknowledgeCritical
+            // ---------------------------------------------------------------------------
+            Msg::AcknowledgeCritical(id) => {
+                match id {
+                    Some(id) => {
+                        self.acknowledged.insert(id);
+                    }
+                    None => self.acknowledged.extend(
+                        self.entries
+                            .iter()
+                            .filter(|entry| entry.severity == Severity::Critical)
+                            .map(|entry| entry.id.clone()),
+                    ),
+                }
+                save_acknowledged(&self.acknowledged);
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: SetDisplayZone
+            // ---------------------------------------------------------------------------
+            Msg::SetDisplayZone(zone) => {
+                self.zone = zone;
+                save_zone(zone);
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: SetLocale
+            // ---------------------------------------------------------------------------
+            Msg::SetLocale(locale) => {
+                self.locale = locale;
+                save_locale(locale);
+                locale.apply();
+                // Rendered again with the placeholders in the language
+                self.rendered.borrow_mut().clear();
+                set_editor_placeholder(
+                    locale.text(Text::EditorPlaceholder),
+                    locale.text(Text::NewEntry),
+                );
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ReceiveTemplates
+            // ---------------------------------------------------------------------------
+            Msg::ReceiveTemplates(templates) => {
+                self.templates = templates;
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: SelectTemplate
+            // ---------------------------------------------------------------------------
+            Msg::SelectTemplate(id) => {
+                self.draft.fields.clear();
+                self.draft.errors.clear();
+                self.draft.template = id;
+                // Presets of the template
+                if let Some(template) = self.selected_template() {
+                    let tags = template.tags.join(", ");
+                    let severity = template.severity;
+                    if self.draft.tags.is_empty() {
+                        self.draft.tags = tags;
+                    }
+                    if let Some(severity) = severity {
+                        self.draft.severity = severity;
+                    }
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: UpdateTemplateField
+            // ---------------------------------------------------------------------------
+            Msg::UpdateTemplateField(name, value) => {
+                self.draft.errors.remove(&name);
+                self.draft.fields.insert(name, value);
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ReplyTo
+            // ---------------------------------------------------------------------------
+            Msg::ReplyTo(id) => {
+                self.draft.reply_to = id;
+                if self.draft.reply_to.is_some() {
+                    focus_editor();
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ToggleThread
+            // ---------------------------------------------------------------------------
+            Msg::ToggleThread(id) => {
+                if !self.expanded_threads.remove(&id) {
+                    self.expanded_threads.insert(id);
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ShowEntry
+            // ---------------------------------------------------------------------------
+            Msg::ShowEntry(id) => {
+                // Not loaded yet, so paged back to until found
+                if self.find_entry(&id).is_none() {
+                    if self.exhausted {
+                        self.missing_entry = Some(id);
+                    } else {
+                        self.missing_entry = None;
+                        self.pending_entry = Some(id);
+                        ctx.link().send_message(Msg::LoadMoreEntries);
+                    }
+                    return true;
+                }
+                // Scroll to its place in the layout, so that it is materialised
+                let top = self
+                    .layout()
+                    .iter()
+                    .find(|row| row.entry.id == id)
+                    .map(|row| row.top);
+                match (top, self.content_ref.cast::<HtmlElement>()) {
+                    (Some(top), Some(content)) => {
+                        content.set_scroll_top(top as i32);
+                        self.update_viewport();
+                        self.highlight = Some(id);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: Scrolled
+            // ---------------------------------------------------------------------------
+            Msg::Scrolled => self.update_viewport(),
+
+            // ---------------------------------------------------------------------------
+            // Message: LayoutChanged
+            // ---------------------------------------------------------------------------
+            Msg::LayoutChanged => true,
+
+            // ---------------------------------------------------------------------------
+            // Message: OpenExport
+            // ---------------------------------------------------------------------------
+            Msg::OpenExport(open) => {
+                self.export = open.then(|| ExportOptions::new(self.zone));
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: UpdateExport
+            // ---------------------------------------------------------------------------
+            Msg::UpdateExport(field, value) => {
+                if let Some(options) = self.export.as_mut() {
+                    options.set(field, value);
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: Export
+            // ---------------------------------------------------------------------------
+            Msg::Export(format) => {
+                let Some(options) = self.export.as_mut() else {
+                    return false;
+                };
+                match options.range(self.locale) {
+                    Ok((from, _)) => {
+                        options.exporting = true;
+                        let link = ctx.link().clone();
+                        let client_hash = self.client_hash.clone();
+                        let zone = self.zone;
+                        spawn_local(async move {
+                            let result = fetch_entries_since(client_hash, from, zone).await;
+                            link.send_message(Msg::ReceiveExportEntries(format, result));
+                        });
+                    }
+                    Err(err) => options.error = Some(err),
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ReceiveExportEntries
+            // ---------------------------------------------------------------------------
+            Msg::ReceiveExportEntries(format, result) => {
+                let Some(options) = self.export.as_ref() else {
+                    return false;
+                };
+                let entries = match result {
+                    Ok(entries) => entries,
+                    Err(err) => {
+                        ctx.link().send_message(Msg::ExportFinished(Err(format!(
+                            "Failed to get the entries: {}",
+                            err
+                        ))));
+                        return false;
+                    }
+                };
+
+                let link = ctx.link().clone();
+                match format {
+                    ReportFormat::Html | ReportFormat::Pdf => {
+                        match self.report_html(&entries, options) {
+                            Ok(html) => {
+                                let filename = format!("{}.html", options.basename());
+                                spawn_local(async move {
+                                    let promise = export_report(
+                                        &html,
+                                        &filename,
+                                        format == ReportFormat::Pdf,
+                                    );
+                                    let result = wasm_bindgen_futures::JsFuture::from(promise)
+                                        .await
+                                        .map(|_| ())
+                                        .map_err(|err| {
+                                            err.as_string()
+                                                .unwrap_or_else(|| "Export failed".to_string())
+                                        });
+                                    link.send_message(Msg::ExportFinished(result));
+                                });
+                            }
+                            Err(err) => link.send_message(Msg::ExportFinished(Err(err))),
+                        }
+                    }
+                    ReportFormat::Archive => match self.archive(&entries, options) {
+                        Ok(archive) => {
+                            let filename = archive.filename();
+                            spawn_local(async move {
+                                let result = archive
+                                    .build()
+                                    .await
+                                    .map(|zip| save_file(&zip, &filename, "application/zip"))
+                                    .map_err(|err| format!("Failed to build the archive: {}", err));
+                                link.send_message(Msg::ExportFinished(result));
+                            });
+                        }
+                        Err(err) => link.send_message(Msg::ExportFinished(Err(err))),
+                    },
+                }
+                false
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ExportFinished
+            // ---------------------------------------------------------------------------
+            Msg::ExportFinished(result) => {
+                match result {
+                    Ok(()) => self.export = None,
+                    Err(err) => {
+                        if let Some(options) = self.export.as_mut() {
+                            options.exporting = false;
+                            options.error = Some(err);
+                        }
+                    }
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: OpenImport
+            // ---------------------------------------------------------------------------
+            Msg::OpenImport(open) => {
+                self.import = open.then(ImportState::default);
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ReadImportFiles
+            // ---------------------------------------------------------------------------
+            Msg::ReadImportFiles(files) => {
+                let Some(state) = self.import.as_mut() else {
+                    return false;
+                };
+                *state = ImportState {
+                    reading: true,
+                    ..Default::default()
+                };
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    let sources = read_source_files(files).await;
+                    link.send_message(Msg::ReceiveImportFiles(sources));
+                });
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ReceiveImportFiles
+            // ---------------------------------------------------------------------------
+            Msg::ReceiveImportFiles(sources) => {
+                let Some(state) = self.import.as_mut() else {
+                    return false;
+                };
+                state.reading = false;
+                state.read(sources);
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: StartImport
+            // ---------------------------------------------------------------------------
+            Msg::StartImport => {
+                let Some(state) = self.import.as_mut() else {
+                    return false;
+                };
+                // One by one in the chronological order; those done already are skipped on retry
+                let forms: Vec<(usize, Result<FormData, String>)> = state
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.status != ImportStatus::Done)
+                    .map(|(i, entry)| {
+                        let form = entry
+                            .form_data()
+                            .map_err(|err| format!("Cannot make the request: {:?}", err));
+                        (i, form)
+                    })
+                    .collect();
+                state.running = true;
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    for (i, form) in forms {
+                        let result = match form {
+                            Ok(form) => post_entry(form).await,
+                            Err(err) => Err(err),
+                        };
+                        link.send_message(Msg::ImportProgress(i, result));
+                    }
+                    link.send_message(Msg::ImportFinished);
+                });
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ImportProgress
+            // ---------------------------------------------------------------------------
+            Msg::ImportProgress(i, result) => {
+                if let Some(entry) = self
+                    .import
+                    .as_mut()
+                    .and_then(|state| state.entries.get_mut(i))
+                {
+                    entry.status = match result {
+                        Ok(()) => ImportStatus::Done,
+                        Err(err) => ImportStatus::Failed(err),
+                    };
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ImportFinished
+            // ---------------------------------------------------------------------------
+            Msg::ImportFinished => {
+                if let Some(state) = self.import.as_mut() {
+                    state.running = false;
+                }
+                // The imported entries go anywhere in the timeline, so it is loaded again
+                self.entries.clear();
+                self.offset = 0;
+                self.exhausted = false;
+                ctx.link().send_message(Msg::GetEntries(self.limit, 0));
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: RunCommand
+            // ---------------------------------------------------------------------------
+            Msg::RunCommand(command) => {
+                // Running a command closes the palette
+                let had_palette = self.palette.take().is_some();
+                match command {
+                    Command::NextEntry => {
+                        self.move_selection(1);
+                    }
+                    Command::PreviousEntry => {
+                        self.move_selection(-1);
+                    }
+                    Command::LatestEntry => {
+                        self.selected = self.layout().last().map(|row| row.entry.id.clone());
+                        self.scroll_to_position(0, true, 0);
+                    }
+                    Command::GoToDate => self.palette = Some(Palette::new(PaletteMode::Date)),
+                    Command::FocusSearch => self.focus_search(),
+                    Command::Reply => {
+                        if let Some(id) = self.selected_entry().map(|entry| entry.id.clone()) {
+                            ctx.link().send_message(Msg::ReplyTo(Some(id)));
+                        }
+                    }
+                    Command::NewEntry => focus_editor(),
+                    Command::OpenAttachments => {
+                        if let Some(id) = self.selected_entry().map(|entry| entry.id.clone()) {
+                            self.toggle_attachments(&id);
+                        }
+                    }
+                    Command::ToggleThread => {
+                        if let Some(id) = self.selected_entry().map(|entry| entry.id.clone()) {
+                            ctx.link().send_message(Msg::ToggleThread(id));
+                        }
+                    }
+                    Command::ClearFilters => {
+                        self.tag_filter.tags.clear();
+                        self.severity_filter = SeverityFilter::default();
+                        self.search.clear();
+                    }
+                    Command::Export => ctx.link().send_message(Msg::OpenExport(true)),
+                    Command::Display => self.preferences_open = true,
+                    Command::Notifications => ctx.link().send_message(Msg::OpenWatchRules(true)),
+                    Command::Import => ctx.link().send_message(Msg::OpenImport(true)),
+                    Command::OpenPalette => {
+                        if !had_palette {
+                            self.palette = Some(Palette::new(PaletteMode::Commands));
+                            self.help = false;
+                        }
+                    }
+                    Command::Help => self.help = !self.help,
+                    Command::Close if had_palette => {}
+                    Command::Close => {
+                        if self.help {
+                            self.help = false;
+                        } else if self.preferences_open {
+                            self.preferences_open = false;
+                        } else if self.watch_draft.is_some() {
+                            self.watch_draft = None;
+                        } else if self.export.is_some() {
+                            ctx.link().send_message(Msg::OpenExport(false));
+                        } else if self.import.is_some() {
+                            ctx.link().send_message(Msg::OpenImport(false));
+                        } else {
+                            self.selected = None;
+                        }
+                    }
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: UpdatePalette / MovePalette / SubmitPalette
+            // ---------------------------------------------------------------------------
+            Msg::UpdatePalette(query) => {
+                if let Some(palette) = self.palette.as_mut() {
+                    palette.query = query;
+                    palette.index = 0;
+                    palette.error = None;
+                }
+                true
+            }
+            Msg::MovePalette(delta) => {
+                if let Some(palette) = self.palette.as_mut() {
+                    let last = palette.commands(self.locale).len().saturating_sub(1);
+                    palette.index = palette.index.saturating_add_signed(delta).min(last);
+                }
+                true
+            }
+            Msg::SubmitPalette => {
+                let Some(palette) = self.palette.as_ref() else {
+                    return false;
+                };
+                match palette.mode {
+                    PaletteMode::Commands => {
+                        if let Some(command) = palette.commands(self.locale).get(palette.index) {
+                            ctx.link().send_message(Msg::RunCommand(*command));
+                        }
+                        false
+                    }
+                    PaletteMode::Date => {
+                        let result = parse_date(&palette.query, self.zone.today())
+                            .ok_or_else(|| format!("{}: {}", self.t(Text::NotADate), palette.query))
+                            .and_then(|date| self.entry_on_date(date));
+                        match result {
+                            Ok(id) => {
+                                self.palette = None;
+                                self.selected = Some(id.clone());
+                                ctx.link().send_message(Msg::ShowEntry(id));
+                            }
+                            Err(err) => {
+                                if let Some(palette) = self.palette.as_mut() {
+                                    palette.error = Some(err);
+                                }
+                            }
+                        }
+                        true
+                    }
+                }
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: UpdateSearch
+            // ---------------------------------------------------------------------------
+            Msg::UpdateSearch(search) => {
+                self.search = search;
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: OpenPreferences / UpdatePreferences
+            // ---------------------------------------------------------------------------
+            Msg::OpenPreferences(open) => {
+                self.preferences_open = open;
+                true
+            }
+            Msg::UpdatePreferences(field, value) => {
+                self.preferences.set(field, &value);
+                self.preferences.save();
+                self.preferences.apply();
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: PollEntries
+            // ---------------------------------------------------------------------------
+            Msg::PollEntries => {
+                // Not while a page is on the way, which would overlap with the new ones,
+                // nor before the first page, to leave the logbook to the paging
+                if self.loading || (self.entries.is_empty() && !self.exhausted) {
+                    return false;
+                }
+                let link = ctx.link().clone();
+                let client_hash = self.client_hash.clone();
+                // Placeholders are named by their position, which the new records move
+                let known: HashSet<String> = self
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.malformed.is_none())
+                    .map(|entry| entry.id.clone())
+                    .collect();
+                spawn_local(async move {
+                    match fetch_new(&client_hash, POLL_LIMIT, &known).await {
+                        Ok(entries) => link.send_message(Msg::ReceiveLiveEntries(entries)),
+                        Err(err) => web_sys::console::log_1(
+                            &format!("Failed to poll the entries: {}", err).into(),
+                        ),
+                    }
+                });
+                false
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: ReceiveLiveEntries
+            // ---------------------------------------------------------------------------
+            Msg::ReceiveLiveEntries(entries) => {
+                // Own entries may have come in between
+                let new: Vec<Entry> = entries
+                    .into_iter()
+                    .filter(|entry| {
+                        entry.malformed.is_some() || self.find_entry(&entry.id).is_none()
+                    })
+                    .collect();
+                // Every new record moves the older pages, the malformed ones too
+                self.offset += new.len() as i64;
+                // Placeholders are named by their position, so they are left to the reload
+                let new: Vec<Entry> = new
+                    .into_iter()
+                    .filter(|entry| entry.malformed.is_none())
+                    .collect();
+                if new.is_empty() {
+                    return false;
+                }
+                self.capture_scroll_anchor();
+                let count = new.len();
+                // Nothing to notify while the page is looked at
+                let attended = page_attended();
+                for entry in new.into_iter().rev() {
+                    if !attended {
+                        if let Some(rule) = matching_rule(&self.watch_rules, &entry) {
+                            self.unread += 1;
+                            notify(&entry, rule, self.locale, ctx.link().clone());
+                        }
+                    }
+                    self.insert_entry(entry);
+                }
+                set_unread_title(self.unread);
+                self.anchor_viewport();
+                self.announcement = format!("{} {}", count, self.t(Text::NewEntries));
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: MarkRead
+            // ---------------------------------------------------------------------------
+            Msg::MarkRead => {
+                if self.unread > 0 {
+                    self.unread = 0;
+                    set_unread_title(0);
+                }
+                false
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: OpenWatchRules / SetWatchKind / UpdateWatchValue
+            // ---------------------------------------------------------------------------
+            Msg::OpenWatchRules(open) => {
+                self.watch_draft = open.then(RuleDraft::default);
+                true
+            }
+            Msg::SetWatchKind(kind) => {
+                if self.watch_draft.is_some() {
+                    self.watch_draft = Some(RuleDraft::with_kind(kind));
+                }
+                true
+            }
+            Msg::UpdateWatchValue(value) => {
+                if let Some(draft) = self.watch_draft.as_mut() {
+                    draft.value = value;
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: AddWatchRule / MuteWatchRule / RemoveWatchRule
+            // ---------------------------------------------------------------------------
+            Msg::AddWatchRule => {
+                let Some(draft) = self.watch_draft.as_mut() else {
+                    return false;
+                };
+                let Some(rule) = draft.rule() else {
+                    return false;
+                };
+                *draft = RuleDraft::with_kind(draft.kind);
+                if !self.watch_rules.contains(&rule) {
+                    self.watch_rules.push(rule);
+                    save_rules(&self.watch_rules);
+                }
+                // The first rule is a good time to ask, from this click
+                if Notification::permission() == NotificationPermission::Default {
+                    request_permission(ctx.link().clone());
+                }
+                true
+            }
+            Msg::MuteWatchRule(index) => {
+                if let Some(rule) = self.watch_rules.get_mut(index) {
+                    rule.muted = !rule.muted;
+                    save_rules(&self.watch_rules);
+                }
+                true
+            }
+            Msg::RemoveWatchRule(index) => {
+                if index < self.watch_rules.len() {
+                    self.watch_rules.remove(index);
+                    save_rules(&self.watch_rules);
+                }
+                true
+            }
+
+            // ---------------------------------------------------------------------------
+            // Message: RequestNotifications / NotificationPermission
+            // ---------------------------------------------------------------------------
+            Msg::RequestNotifications => {
+                request_permission(ctx.link().clone());
+                false
+            }
+            // Answered, the dialog shows the new state
+            Msg::NotificationPermission => true,
         }
     }
 
@@ -262,13 +1265,8 @@ impl Component for Model {
 
             // Scroll event listener
             let link = ctx.link().clone();
-            let content_ref = self.content_ref.clone();
             let callback = Closure::<dyn Fn()>::new(move || {
-                if let Some(content) = content_ref.cast::<HtmlElement>() {
-                    if content.scroll_top() == 0 {
-                        link.send_message(Msg::LoadMoreEntries);
-                    }
-                }
+                link.send_message(Msg::Scrolled);
             });
 
             // Listen scroll event
@@ -278,64 +1276,355 @@ impl Component for Model {
                     .unwrap();
             }
             callback.forget();
+
+            // Load older entries when the top of the timeline comes into view
+            let link = ctx.link().clone();
+            let on_intersect =
+                Closure::<dyn Fn(js_sys::Array)>::new(move |records: js_sys::Array| {
+                    let intersecting = records.iter().any(|record| {
+                        record
+                            .unchecked_into::<web_sys::IntersectionObserverEntry>()
+                            .is_intersecting()
+                    });
+                    if intersecting {
+                        link.send_message(Msg::LoadMoreEntries);
+                    }
+                });
+            let options = web_sys::IntersectionObserverInit::new();
+            options.set_root(self.content_ref.cast::<web_sys::Element>().as_ref());
+            options.set_root_margin("400px 0px 0px 0px");
+            self.sentinel_observer = web_sys::IntersectionObserver::new_with_options(
+                on_intersect.as_ref().unchecked_ref(),
+                &options,
+            )
+            .ok();
+            on_intersect.forget();
+            self.observe_sentinel();
+
+            // Links to entries (#entry-<id>)
+            self.pending_entry = entry_id_from_hash();
+            let link = ctx.link().clone();
+            let on_hash_change = Closure::<dyn Fn()>::new(move || {
+                if let Some(id) = entry_id_from_hash() {
+                    link.send_message(Msg::ShowEntry(id));
+                }
+            });
+            web_sys::window()
+                .unwrap()
+                .add_event_listener_with_callback(
+                    "hashchange",
+                    on_hash_change.as_ref().unchecked_ref(),
+                )
+                .unwrap();
+            on_hash_change.forget();
+        }
+
+        // Virtualised timeline: keep the place, measure the new rows and re-layout if needed
+        self.rendered_range = visible_range(&self.layout(), &self.viewport);
+        self.restore_scroll_anchor();
+        let measured = self.measure_rows();
+        if measured {
+            self.capture_scroll_anchor();
+        }
+        if measured || self.update_viewport() {
+            ctx.link().send_message(Msg::LayoutChanged);
+        }
+
+        if self.palette.is_some() {
+            self.focus_palette();
+        } else {
+            self.focus_selected();
+        }
+
+        // The entry to show, once it is materialised
+        if let Some(id) = self.highlight.take() {
+            let element = web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|document| document.get_element_by_id(&format!("entry-{}", id)));
+            if let Some(element) = element {
+                element.scroll_into_view();
+                let _ = element.class_list().add_1("highlighted");
+                Timeout::new(2000, move || {
+                    let _ = element.class_list().remove_1("highlighted");
+                })
+                .forget();
+            }
         }
     }
 
     /////////////////////////////////////////////////////////////////////////////////////////////
     /// view
     /////////////////////////////////////////////////////////////////////////////////////////////
-    fn view(&self, _ctx: &Context<Self>) -> Html {
-        let mut last_date = None;
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        // Only the rows around the viewport are materialised between two spacers
+        let rows = self.layout();
+        let range = visible_range(&rows, &self.viewport);
+        let total_height = rows.last().map_or(0.0, |row| row.bottom());
+        let (top_space, bottom_space) = match (
+            rows.get(range.start),
+            range.end.checked_sub(1).and_then(|i| rows.get(i)),
+        ) {
+            (Some(first), Some(last)) => {
+                // The first row always shows its date to keep the sticky header
+                let forced_date = if first.show_date {
+                    0.0
+                } else {
+                    self.row_heights.date()
+                };
+                (
+                    (first.top - forced_date).max(0.0),
+                    total_height - last.bottom(),
+                )
+            }
+            _ => (0.0, total_height),
+        };
+        // Unknown until the beginning of the logbook is loaded
+        let set_size = if self.exhausted {
+            rows.len().to_string()
+        } else {
+            "-1".to_string()
+        };
 
         html! {
             <div class="container">
                 <header class="header">
-                    <h1>{"Elogbook Entries"}</h1>
+                    <h1>{self.t(Text::Title)}</h1>
+                    <div class="header-buttons">
+                        {self.locale_selector(ctx)}
+                        {self.zone_selector(ctx)}
+                        <button class="header-button" title={self.t(Text::DisplaySettings)} aria-label={self.t(Text::DisplaySettings)} onclick={ctx.link().callback(|_| Msg::OpenPreferences(true))}>
+                            <i class="material-symbols-outlined" aria-hidden="true">{"contrast"}</i>
+                        </button>
+                        <button
+                            class="header-button watch-button"
+                            title={self.t(Text::Notifications)}
+                            aria-label={self.t(Text::Notifications)}
+                            onclick={ctx.link().callback(|_| Msg::OpenWatchRules(true))}
+                        >
+                            <i class="material-symbols-outlined" aria-hidden="true">{"notifications"}</i>
+                            if self.unread > 0 {
+                                <span class="unread-count">{ self.unread }</span>
+                            }
+                        </button>
+                        <button class="header-button" title={self.t(Text::KeyboardShortcuts)} aria-label={self.t(Text::KeyboardShortcuts)} onclick={ctx.link().callback(|_| Msg::RunCommand(Command::Help))}>
+                            <i class="material-symbols-outlined" aria-hidden="true">{"keyboard"}</i>
+                        </button>
+                        <button class="header-button" title={self.t(Text::ImportEntries)} onclick={ctx.link().callback(|_| Msg::OpenImport(true))}>
+                            <i class="material-symbols-outlined" aria-hidden="true">{"upload_file"}</i>
+                            {self.t(Text::Import)}
+                        </button>
+                        <button class="header-button" title={self.t(Text::ExportReport)} onclick={ctx.link().callback(|_| Msg::OpenExport(true))}>
+                            <i class="material-symbols-outlined" aria-hidden="true">{"description"}</i>
+                            {self.t(Text::Export)}
+                        </button>
+                    </div>
+                    {self.api_banner(ctx)}
+                    {self.missing_entry_banner(ctx)}
+                    {self.critical_banner(ctx)}
                 </header>
-                <div ref={self.content_ref.clone()} id="content" class="content">
-                    <ul class="entries-list">
-                        {
-                            for self.entries.iter().map(|entry| {
-                                let entry_date = entry.timestamp.with_timezone(&Local).date_naive();
-                                let show_date = match last_date {
-                                    Some(last) if last == entry_date => false,
-                                    _ => {
-                                        last_date = Some(entry_date);
-                                        true
-                                    }
-                                };
-                                html! {
-                                    <>
+                <div class="main">
+                    <main ref={self.content_ref.clone()} id="content" class="content">
+                        // A feed of articles: the day headers are not list items, and stay sticky
+                        <div
+                            class="entries-list"
+                            role="feed"
+                            aria-label={self.t(Text::Timeline)}
+                            aria-busy={self.loading.to_string()}
+                        >
+                            <div ref={self.sentinel_ref.clone()} class="load-sentinel">
+                                if self.loading {
+                                    <span class="loading-indicator">
+                                        <i class="material-symbols-outlined" aria-hidden="true">{"progress_activity"}</i>
+                                        {self.t(Text::LoadingOlder)}
+                                    </span>
+                                } else if self.exhausted && !self.entries.is_empty() {
+                                    <span class="timeline-start">{self.t(Text::BeginningOfLogbook)}</span>
+                                }
+                            </div>
+                            <div class="virtual-spacer" aria-hidden="true" style={format!("height: {}px", top_space)}/>
+                            {
+                                for rows[range.clone()].iter().enumerate().map(|(i, row)| {
+                                    let entry = row.entry;
+                                    let entry_date = row.date;
+                                    let show_date = row.show_date || i == 0;
+                                    let date_header = html! {
                                         if show_date {
-                                            <div class="entry-date">{ entry_date.format("%Y-%m-%d").to_string() }</div>
-                                            <div class="entry-date-boader"/>
+                                            <h2 id={format!("date-{}", entry.id)} class="entry-date">
+                                                <time datetime={entry_date.format("%Y-%m-%d").to_string()}>{ self.locale.date(entry_date) }</time>
+                                            </h2>
+                                            <div class="entry-date-boader" aria-hidden="true"/>
                                         }
-                                        <li class="entry-item">
-                                            <span class="timestamp">
-                                                { entry.timestamp.with_timezone(&Local).format("%H:%M:%S").to_string() }
-                                            </span>
-                                            <span class="log-text">{self.markdown_to_html(entry)}</span>
-                                        </li>
-                                    </>
-                                }
-                            })
-                        }
-                    </ul>
+                                    };
+                                    if let Some(error) = &entry.malformed {
+                                        return html! {
+                                            <key={entry.id.clone()}>
+                                                {date_header}
+                                                {self.malformed_entry(entry, error)}
+                                            </>
+                                        };
+                                    }
+                                    let (log_html, referenced) = self.markdown_to_html(entry);
+                                    let selected = self.selected.as_ref() == Some(&entry.id);
+                                    // Keyed, so that the rendered entries move with the list
+                                    html! {
+                                        <key={entry.id.clone()}>
+                                            {date_header}
+                                            <article
+                                                id={format!("entry-{}", entry.id)}
+                                                class={classes!("entry-item", entry.severity.as_str(), selected.then_some("selected"))}
+                                                tabindex="-1"
+                                                aria-label={self.entry_label(entry, entry_date)}
+                                                aria-posinset={(range.start + i + 1).to_string()}
+                                                aria-setsize={set_size.clone()}
+                                                aria-current={selected.then_some("true")}
+                                                data-date={entry_date.format("%Y-%m-%d").to_string()}
+                                                data-author={entry.author.clone()}
+                                            >
+                                                <time
+                                                    class="timestamp"
+                                                    datetime={entry.timestamp.to_rfc3339()}
+                                                    title={format!("{}\n{} {}", utc_title(&entry.timestamp), self.t(Text::Submitted), utc_title(&entry.created_at))}
+                                                >
+                                                    { self.zone.format(&entry.timestamp, "%H:%M:%S") }
+                                                </time>
+                                                <div class="log-text">
+                                                    {self.reply_link(ctx, entry)}
+                                                    if let Some(author) = &entry.author {
+                                                        <span class="entry-author">{ author }</span>
+                                                    }
+                                                    {self.severity_badge(entry)}
+                                                    {self.backdated_badge(entry)}
+                                                    {log_html}
+                                                    {self.attachment_strip(ctx, entry, &referenced)}
+                                                    {self.tag_chips(ctx, entry)}
+                                                    {self.thread_view(ctx, entry)}
+                                                </div>
+                                                {self.entry_actions(ctx, entry)}
+                                            </article>
+                                        </>
+                                    }
+                                })
+                            }
+                            <div class="virtual-spacer" aria-hidden="true" style={format!("height: {}px", bottom_space)}/>
+                        </div>
+                    </main>
+                    <aside class="sidebar" aria-label={self.t(Text::Filters)}>
+                        {self.search_box(ctx)}
+                        {self.severity_sidebar(ctx)}
+                        {self.tag_sidebar(ctx)}
+                    </aside>
                 </div>
                 <div id="file-previews" class="file-previews"></div>
                 <div class="resize-divider"></div>
-                <footer class="footer">
+                {self.reply_banner(ctx)}
+                {self.template_form(ctx)}
+                {self.export_dialog(ctx)}
+                {self.import_dialog(ctx)}
+                {self.preferences_dialog(ctx)}
+                {self.watch_dialog(ctx)}
+                {self.command_palette(ctx)}
+                {self.help_overlay(ctx)}
+                <div class="visually-hidden" role="status" aria-live="polite">{ &self.announcement }</div>
+                <footer class="footer" role="form" aria-label={self.t(Text::NewEntry)}>
                     <textarea
                         value=""
                         class="input-box"
-                        placeholder="Enter text here..."
+                        aria-label={self.t(Text::NewEntry)}
+                        placeholder={self.t(Text::EditorPlaceholder)}
                     />
+                    <div class="entry-meta">
+                        if !self.templates.is_empty() {
+                            <select
+                                class="template-select"
+                                aria-label={self.t(Text::Template)}
+                                onchange={ctx.link().callback(|e: Event| {
+                                    let id = e.target_unchecked_into::<HtmlSelectElement>().value();
+                                    Msg::SelectTemplate((!id.is_empty()).then_some(id))
+                                })}
+                            >
+                                <option value="" selected={self.draft.template.is_none()}>{self.t(Text::NoTemplate)}</option>
+                                {
+                                    for self.templates.iter().map(|template| html! {
+                                        <option
+                                            value={template.id.clone()}
+                                            selected={self.draft.template.as_ref() == Some(&template.id)}
+                                        >
+                                            { &template.name }
+                                        </option>
+                                    })
+                                }
+                            </select>
+                        }
+                        <select
+                            class={classes!("severity-select", self.draft.severity.as_str())}
+                            aria-label={self.t(Text::Severity)}
+                            onchange={ctx.link().callback(|e: Event| {
+                                Msg::UpdateDraftSeverity(Severity::parse(&e.target_unchecked_into::<HtmlSelectElement>().value()))
+                            })}
+                        >
+                            {
+                                for Severity::ALL.into_iter().map(|severity| html! {
+                                    <option value={severity.as_str()} selected={severity == self.draft.severity}>
+                                        { self.t(Text::SeverityName(severity)) }
+                                    </option>
+                                })
+                            }
+                        </select>
+                        <input
+                            type="datetime-local"
+                            class={classes!("event-time-input", self.draft.event_error.is_some().then_some("invalid"))}
+                            aria-label={self.t(Text::EventTime)}
+                            aria-invalid={self.draft.event_error.is_some().then_some("true")}
+                            title={self.draft.event_error.clone().unwrap_or_else(|| format!("{} ({})", self.t(Text::EventTime), self.zone.label(self.locale)))}
+                            value={self.draft.event_time.clone()}
+                            oninput={ctx.link().callback(|e: InputEvent| {
+                                Msg::UpdateDraftEventTime(e.target_unchecked_into::<HtmlInputElement>().value())
+                            })}
+                        />
+                        if let Some(error) = &self.draft.event_error {
+                            <span class="event-time-error" role="alert">{ error }</span>
+                        }
+                        if let Some(error) = &self.draft.submit_error {
+                            <span class="submit-error" role="alert">{ format!("{}: {}", self.t(Text::EntryNotSent), error) }</span>
+                        }
+                        <input
+                            class="tag-input"
+                            aria-label={self.t(Text::Tags)}
+                            placeholder={self.t(Text::TagsPlaceholder)}
+                            value={self.draft.tags.clone()}
+                            oninput={ctx.link().callback(|e: InputEvent| {
+                                Msg::UpdateDraftTags(e.target_unchecked_into::<HtmlInputElement>().value())
+                            })}
+                        />
+                    </div>
                 </footer>
             </div>
         }
     }
 }
 
+// The entry id in the URL hash (#entry-<id>)
+fn entry_id_from_hash() -> Option<String> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    hash.strip_prefix("#entry-")
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
+
+// Functions of Java Script
+#[wasm_bindgen]
+extern "C" {
+    // Empty the editor and the staged attachments after the submission (textarea.js)
+    #[wasm_bindgen(js_name = clearEditor)]
+    fn clear_editor();
+
+    #[wasm_bindgen(js_name = focusEditor)]
+    fn focus_editor();
+
+    #[wasm_bindgen(js_name = setEditorPlaceholder)]
+    fn set_editor_placeholder(text: &str, label: &str);
+}
+
 // Interface to Java Script
 fn register_entry_callback(link: yew::html::Scope<Model>) {
     // Make a closure and open it to JavaScript
@@ -361,6 +1650,32 @@ fn register_entry_callback(link: yew::html::Scope<Model>) {
     callback.forget();
 }
 
+// Send an entry to the backend
+async fn post_entry(form_data: FormData) -> Result<(), String> {
+    let request_init = web_sys::RequestInit::new();
+    request_init.set_method("POST");
+    request_init.set_body(&JsValue::from(form_data));
+
+    let request =
+        web_sys::Request::new_with_str_and_init("http://127.0.0.1:8080/add_entry", &request_init)
+            .unwrap();
+
+    let window = web_sys::window().unwrap();
+    let fetch_promise = window.fetch_with_request(&request);
+
+    match wasm_bindgen_futures::JsFuture::from(fetch_promise).await {
+        Ok(response) => {
+            let response: web_sys::Response = response.dyn_into().unwrap();
+            if response.ok() {
+                Ok(())
+            } else {
+                Err("Request failed.".to_string())
+            }
+        }
+        Err(err) => Err(format!("Request failed: {:?}", err)),
+    }
+}
+
 fn main() {
     yew::Renderer::<Model>::new().render();
 }
@@ -1,7 +1,26 @@
-use chrono::{DateTime, Local};
-use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Deserializer};
 use yew::prelude::*;
 
+use crate::api::*;
+use crate::export::*;
+use crate::i18n::*;
+use crate::import::*;
+use crate::keyboard::*;
+use crate::notifications::*;
+use crate::preferences::*;
+use crate::render::*;
+use crate::severity::*;
+use crate::tags::*;
+use crate::templates::*;
+use crate::timezone::*;
+use crate::virtual_list::*;
+
 // From the backend
 #[derive(Debug, Deserialize)]
 pub struct Attachment {
@@ -9,37 +28,130 @@ pub struct Attachment {
     pub mime: String,
     pub original_name: String,
     pub download_token: String,
+    #[serde(default)]
+    pub size: Option<u64>,
 }
 pub type Attachments = Vec<Attachment>;
 
+// Unknown fields are ignored, and missing or null ones taken as empty
 #[derive(Debug, Deserialize)] // Deserializeを追加
 pub struct EntryResponse {
     pub id: String,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub content: String,
     pub created_at: String,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub attachments: Attachments,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub tags: Vec<String>,
+    // Unknown severities are taken as Info
+    #[serde(default)]
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub fields: Option<EntryFields>,
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    // Time of the event, when logged after the fact
+    #[serde(default)]
+    pub event_at: Option<String>,
 }
 impl EntryResponse {
-    pub fn to_entry(self) -> Option<Entry> {
-        if let Ok(datetime) = DateTime::parse_from_rfc3339(&self.created_at) {
-            Some(Entry {
-                id: self.id,
-                log: self.content,
-                timestamp: datetime.with_timezone(&Local),
-                attachments: self.attachments,
-            })
-        } else {
-            None
-        }
+    pub fn into_entry(self) -> Result<Entry, String> {
+        let created_at = DateTime::parse_from_rfc3339(&self.created_at)
+            .map_err(|err| format!("invalid created_at `{}`: {}", self.created_at, err))?
+            .with_timezone(&Local);
+        let timestamp = match self.event_at.as_deref() {
+            Some(event_at) => DateTime::parse_from_rfc3339(event_at)
+                .map_err(|err| format!("invalid event_at `{}`: {}", event_at, err))?
+                .with_timezone(&Local),
+            None => created_at,
+        };
+        let explicit = self.tags.iter().map(|tag| normalize_tag(tag)).collect();
+        let tags = merge_tags(explicit, parse_hashtags(&self.content));
+        Ok(Entry {
+            revision: revision(&self.content, &self.attachments),
+            id: self.id,
+            log: self.content,
+            timestamp,
+            created_at,
+            attachments: self.attachments,
+            tags,
+            severity: self
+                .severity
+                .as_deref()
+                .map(Severity::parse)
+                .unwrap_or_default(),
+            fields: self.fields,
+            parent: self.parent,
+            author: self.author,
+            malformed: None,
+        })
     }
 }
 
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+// The records of a page from `get_entries`, starting at `offset`
+// A record which cannot be read becomes a placeholder, so that nothing is dropped
+// silently and the page keeps its length for the paging
+pub fn parse_page(records: Vec<serde_json::Value>, offset: i64) -> Vec<Entry> {
+    let results: Vec<(serde_json::Value, Result<Entry, String>)> = records
+        .into_iter()
+        .map(|record| {
+            let entry = EntryResponse::deserialize(&record)
+                .map_err(|err| err.to_string())
+                .and_then(EntryResponse::into_entry);
+            (record, entry)
+        })
+        .collect();
+
+    // Placeholders take the submission time of the newer record, to stay where they were
+    let mut time = results
+        .iter()
+        .find_map(|(_, entry)| entry.as_ref().ok().map(|entry| entry.created_at))
+        .unwrap_or_else(Local::now);
+    results
+        .into_iter()
+        .enumerate()
+        .map(|(i, (record, entry))| match entry {
+            Ok(entry) => {
+                time = entry.created_at;
+                entry
+            }
+            Err(err) => Entry::malformed(offset + i as i64, &record, err, time),
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 pub struct Entry {
     pub id: String,
+    // Changes whenever what is rendered from the entry changes
+    pub revision: u64,
     pub log: String,
+    // Time of the event, ordering and grouping the entries
     pub timestamp: DateTime<Local>,
+    // Time the entry was submitted
+    pub created_at: DateTime<Local>,
     pub attachments: Attachments,
+    // Explicit tags and #hashtags in the log
+    pub tags: Vec<String>,
+    pub severity: Severity,
+    // Field values of a templated entry
+    pub fields: Option<EntryFields>,
+    // Id of the entry this replies to
+    pub parent: Option<String>,
+    pub author: Option<String>,
+    // Why the backend record could not be read, the log holding the raw record
+    pub malformed: Option<String>,
 }
 impl Entry {
     pub fn new(
@@ -49,12 +161,100 @@ impl Entry {
         attachments: Attachments,
     ) -> Self {
         Self {
-            id: id,
-            log: log,
-            timestamp: timestamp,
-            attachments: attachments,
+            id,
+            revision: revision(&log, &attachments),
+            tags: parse_hashtags(&log),
+            log,
+            timestamp,
+            created_at: timestamp,
+            attachments,
+            severity: Severity::default(),
+            fields: None,
+            parent: None,
+            author: None,
+            malformed: None,
         }
     }
+
+    // Placeholder of a record which could not be read, `position` in the pages
+    pub fn malformed(
+        position: i64,
+        record: &serde_json::Value,
+        error: String,
+        timestamp: DateTime<Local>,
+    ) -> Self {
+        let raw = serde_json::to_string_pretty(record).unwrap_or_else(|_| record.to_string());
+        // By the backend id if it is there at all
+        let id = match record.get("id") {
+            Some(serde_json::Value::String(id)) => id.clone(),
+            Some(id) if !id.is_null() => id.to_string(),
+            _ => format!("at-{}", position),
+        };
+        Self {
+            id: format!("malformed-{}", id),
+            revision: 0,
+            log: raw,
+            timestamp,
+            created_at: timestamp,
+            attachments: vec![],
+            tags: vec![],
+            severity: Severity::default(),
+            fields: None,
+            parent: None,
+            author: None,
+            malformed: Some(error),
+        }
+    }
+
+    // Logged noticeably after the event
+    pub fn backdated(&self) -> bool {
+        self.created_at - self.timestamp > Duration::minutes(BACKDATED_MINUTES)
+    }
+
+    // The first non-empty line of the log, cut at `max` characters
+    pub fn summary(&self, max: usize) -> String {
+        let line = self
+            .log
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("");
+        if line.chars().count() > max {
+            format!("{}…", line.chars().take(max).collect::<String>())
+        } else {
+            line.to_string()
+        }
+    }
+}
+
+// Delay from the event time after which an entry is flagged as backdated
+const BACKDATED_MINUTES: i64 = 10;
+
+fn revision(log: &str, attachments: &Attachments) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    log.hash(&mut hasher);
+    for attachment in attachments.iter() {
+        attachment.download_token.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+// Metadata of the entry being written, besides the editor content
+#[derive(Debug, Default)]
+pub struct Draft {
+    pub tags: String,
+    pub severity: Severity,
+    // Id of the template in use, with its inputs and validation errors by field name
+    pub template: Option<String>,
+    pub fields: BTreeMap<String, String>,
+    pub errors: BTreeMap<String, String>,
+    // Id of the entry being replied to
+    pub reply_to: Option<String>,
+    // Time of the event as typed (YYYY-MM-DDTHH:MM in the display zone), now if empty
+    pub event_time: String,
+    pub event_error: Option<String>,
+    // Why the last submission failed, kept until the next one
+    pub submit_error: Option<String>,
 }
 
 pub struct Model {
@@ -63,6 +263,67 @@ pub struct Model {
     pub limit: i64,
     pub offset: i64,
     pub loading: bool,
+    // No older entries to load
+    pub exhausted: bool,
     pub content_ref: NodeRef,
+    // Top of the timeline, loading older entries when it comes into view
+    pub sentinel_ref: NodeRef,
+    pub sentinel_observer: Option<web_sys::IntersectionObserver>,
+    // Virtualised rendering of the timeline
+    pub viewport: Viewport,
+    pub rendered_range: Range<usize>,
+    pub row_heights: RowHeights,
+    pub scroll_anchor: Option<ScrollAnchor>,
+    // Entry to highlight once materialised
+    pub highlight: Option<String>,
+    // Rendered markdown by entry id, reused until the entry revision changes
+    pub rendered: RefCell<HashMap<String, RenderedEntry>>,
+    // Download tokens of the attachments opened from the attachment strips
+    pub opened_attachments: HashSet<String>,
+    // Ids of the entries showing their replies
+    pub expanded_threads: HashSet<String>,
+    pub tag_filter: TagFilter,
+    pub severity_filter: SeverityFilter,
+    // Ids of the critical entries already acknowledged
+    pub acknowledged: HashSet<String>,
+    // Zone the times are shown and grouped by day in
+    pub zone: DisplayZone,
+    // Language of the UI
+    pub locale: Locale,
+    // Read out by the screen readers (live region)
+    pub announcement: String,
+    // Mismatch with the backend found at startup
+    pub api_warning: Option<ApiWarning>,
+    // Entry selected from the keyboard
+    pub selected: Option<String>,
+    // Command palette and the key bindings help, while open
+    pub palette: Option<Palette>,
+    pub palette_ref: NodeRef,
+    pub help: bool,
+    // Words searched for in the loaded entries
+    pub search: String,
+    pub search_ref: NodeRef,
+    // Theme and density, and their dialog
+    pub preferences: Preferences,
+    pub preferences_open: bool,
+    // Rules of the desktop notifications, and their dialog while open
+    pub watch_rules: Vec<WatchRule>,
+    pub watch_draft: Option<RuleDraft>,
+    // Entries notified since the page was last looked at
+    pub unread: usize,
+    pub draft: Draft,
+    pub templates: Vec<Template>,
+    // Export dialog, while open
+    pub export: Option<ExportOptions>,
+    // Import wizard, while open
+    pub import: Option<ImportState>,
+    // Entry to show once loaded, given by the URL hash (#entry-<id>)
+    pub pending_entry: Option<String>,
+    // Entry linked to but not found back to the beginning of the logbook
+    pub missing_entry: Option<String>,
+    // Kept only to hold the timers alive
+    #[allow(dead_code)]
     pub interval: Option<gloo_timers::callback::Interval>,
+    #[allow(dead_code)]
+    pub poll_interval: Option<gloo_timers::callback::Interval>,
 }
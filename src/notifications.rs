@@ -0,0 +1,354 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    HtmlInputElement, HtmlSelectElement, KeyboardEvent, Notification, NotificationOptions,
+    NotificationPermission,
+};
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::severity::*;
+use crate::tags::*;
+use crate::Msg;
+
+// Interval of looking for the entries logged by others, in ms
+pub const POLL_INTERVAL: u32 = 15_000;
+// Entries fetched a page at a time, back to the ones already loaded
+pub const POLL_LIMIT: i64 = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleKind {
+    #[default]
+    Tag,
+    // The severity or a higher one
+    Severity,
+    Keyword,
+    Author,
+}
+
+impl RuleKind {
+    pub const ALL: [RuleKind; 4] = [
+        RuleKind::Tag,
+        RuleKind::Severity,
+        RuleKind::Keyword,
+        RuleKind::Author,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleKind::Tag => "tag",
+            RuleKind::Severity => "severity",
+            RuleKind::Keyword => "keyword",
+            RuleKind::Author => "author",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+            .unwrap_or_default()
+    }
+
+    // Value a new rule of the kind starts with
+    fn initial_value(&self) -> String {
+        match self {
+            RuleKind::Severity => Severity::Critical.as_str().to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+// Entries to be notified of
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchRule {
+    pub kind: RuleKind,
+    pub value: String,
+    #[serde(default)]
+    pub muted: bool,
+}
+
+impl WatchRule {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let value = self.value.trim().to_lowercase();
+        match self.kind {
+            RuleKind::Tag => entry.tags.contains(&normalize_tag(&value)),
+            RuleKind::Severity => entry.severity >= Severity::parse(&value),
+            RuleKind::Keyword => entry.log.to_lowercase().contains(&value),
+            RuleKind::Author => entry
+                .author
+                .as_deref()
+                .is_some_and(|author| author.trim().to_lowercase() == value),
+        }
+    }
+
+    pub fn describe(&self, locale: Locale) -> String {
+        match self.kind {
+            RuleKind::Tag => format!("#{}", normalize_tag(&self.value)),
+            RuleKind::Severity => format!(
+                "{} {}",
+                locale.text(Text::SeverityName(Severity::parse(&self.value))),
+                locale.text(Text::OrHigher)
+            ),
+            RuleKind::Keyword => format!("\"{}\"", self.value.trim()),
+            RuleKind::Author => format!("{} {}", locale.text(Text::By), self.value.trim()),
+        }
+    }
+}
+
+// The first rule, not muted, which the entry matches
+pub fn matching_rule<'a>(rules: &'a [WatchRule], entry: &Entry) -> Option<&'a WatchRule> {
+    rules.iter().find(|rule| !rule.muted && rule.matches(entry))
+}
+
+// Rule being written in the dialog
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleDraft {
+    pub kind: RuleKind,
+    pub value: String,
+}
+
+impl RuleDraft {
+    pub fn with_kind(kind: RuleKind) -> Self {
+        Self {
+            kind,
+            value: kind.initial_value(),
+        }
+    }
+
+    pub fn rule(&self) -> Option<WatchRule> {
+        let value = self.value.trim();
+        (!value.is_empty()).then(|| WatchRule {
+            kind: self.kind,
+            value: value.to_string(),
+            muted: false,
+        })
+    }
+}
+
+// The rules are kept in this browser, as they belong to the person on call
+const RULES_KEY: &str = "elogbook.watch_rules";
+
+pub fn load_rules() -> Vec<WatchRule> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(RULES_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_rules(rules: &[WatchRule]) {
+    if let Some(storage) =
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    {
+        let json = serde_json::to_string(rules).unwrap_or_default();
+        let _ = storage.set_item(RULES_KEY, &json);
+    }
+}
+
+// Whether the page is being looked at, when nothing needs to be notified
+pub fn page_attended() -> bool {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .is_some_and(|document| !document.hidden() && document.has_focus().unwrap_or(true))
+}
+
+// Browser notification of the entry, showing it when clicked
+pub fn notify(entry: &Entry, rule: &WatchRule, locale: Locale, link: yew::html::Scope<Model>) {
+    if Notification::permission() != NotificationPermission::Granted {
+        return;
+    }
+    let title = format!(
+        "{} · {}",
+        rule.describe(locale),
+        entry
+            .author
+            .as_deref()
+            .unwrap_or(locale.text(Text::NewEntry))
+    );
+    let options = NotificationOptions::new();
+    options.set_body(&entry.summary(120));
+    // Replaces an earlier notification of the same entry
+    options.set_tag(&entry.id);
+    let Ok(notification) = Notification::new_with_options(&title, &options) else {
+        return;
+    };
+    let id = entry.id.clone();
+    let onclick = Closure::once_into_js(move || {
+        if let Some(window) = web_sys::window() {
+            let _ = window.focus();
+        }
+        link.send_message(Msg::ShowEntry(id));
+    });
+    notification.set_onclick(Some(onclick.unchecked_ref()));
+}
+
+// Asked from a click, as the browsers require
+pub fn request_permission(link: yew::html::Scope<Model>) {
+    let Ok(promise) = Notification::request_permission() else {
+        return;
+    };
+    spawn_local(async move {
+        let _ = JsFuture::from(promise).await;
+        link.send_message(Msg::NotificationPermission);
+    });
+}
+
+// Unread count in front of the page title, e.g. "(3) Elogbook"
+pub fn set_unread_title(unread: usize) {
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        return;
+    };
+    let title = document.title();
+    let base = title
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(") "))
+        .filter(|(count, _)| count.parse::<usize>().is_ok())
+        .map_or(title.as_str(), |(_, base)| base);
+    if unread > 0 {
+        document.set_title(&format!("({}) {}", unread, base));
+    } else {
+        document.set_title(base);
+    }
+}
+
+// Back to the page, the notified entries are read
+pub fn register_attention(link: yew::html::Scope<Model>) {
+    let callback = Closure::<dyn Fn()>::new(move || {
+        if page_attended() {
+            link.send_message(Msg::MarkRead);
+        }
+    });
+    if let Some(window) = web_sys::window() {
+        let _ = window.add_event_listener_with_callback("focus", callback.as_ref().unchecked_ref());
+        if let Some(document) = window.document() {
+            let _ = document.add_event_listener_with_callback(
+                "visibilitychange",
+                callback.as_ref().unchecked_ref(),
+            );
+        }
+    }
+    callback.forget();
+}
+
+impl Model {
+    // Dialog of the watch rules
+    pub fn watch_dialog(&self, ctx: &Context<Self>) -> Html {
+        let Some(draft) = &self.watch_draft else {
+            return html! {};
+        };
+        let permission = match Notification::permission() {
+            NotificationPermission::Granted => html! {},
+            NotificationPermission::Denied => html! {
+                <div class="dialog-error">
+                    { self.t(Text::NotificationsBlocked) }
+                </div>
+            },
+            _ => html! {
+                <div class="dialog-status watch-permission">
+                    <span>{ self.t(Text::NotificationsNotAllowed) }</span>
+                    <button onclick={ctx.link().callback(|_| Msg::RequestNotifications)}>
+                        { self.t(Text::AllowNotifications) }
+                    </button>
+                </div>
+            },
+        };
+        let onkeydown = ctx
+            .link()
+            .batch_callback(|e: KeyboardEvent| (e.key() == "Enter").then_some(Msg::AddWatchRule));
+        let value_input = if draft.kind == RuleKind::Severity {
+            html! {
+                <select
+                    aria-label={self.t(Text::Severity)}
+                    onchange={ctx.link().callback(|e: Event| {
+                        Msg::UpdateWatchValue(e.target_unchecked_into::<HtmlSelectElement>().value())
+                    })}
+                >
+                    {
+                        for Severity::ALL.into_iter().map(|severity| html! {
+                            <option value={severity.as_str()} selected={severity.as_str() == draft.value}>
+                                { format!("{} {}", self.t(Text::SeverityName(severity)), self.t(Text::OrHigher)) }
+                            </option>
+                        })
+                    }
+                </select>
+            }
+        } else {
+            html! {
+                <input
+                    type="text"
+                    aria-label={self.t(Text::RuleKindName(draft.kind))}
+                    placeholder={self.t(Text::RulePlaceholder(draft.kind))}
+                    value={draft.value.clone()}
+                    oninput={ctx.link().callback(|e: InputEvent| {
+                        Msg::UpdateWatchValue(e.target_unchecked_into::<HtmlInputElement>().value())
+                    })}
+                    {onkeydown}
+                />
+            }
+        };
+
+        html! {
+            <div class="dialog-backdrop">
+                <div class="dialog watch-dialog" role="dialog" aria-modal="true" aria-label={self.t(Text::Notifications)}>
+                    <div class="dialog-header">
+                        <span>{ self.t(Text::Notifications) }</span>
+                        <button class="close-button" aria-label={self.t(Text::Close)} onclick={ctx.link().callback(|_| Msg::OpenWatchRules(false))}>
+                            {"×"}
+                        </button>
+                    </div>
+                    {permission}
+                    <ul class="watch-rules">
+                        if self.watch_rules.is_empty() {
+                            <li class="watch-empty">{ self.t(Text::NoRules) }</li>
+                        }
+                        {
+                            for self.watch_rules.iter().enumerate().map(|(i, rule)| html! {
+                                <li class={classes!("watch-rule", rule.muted.then_some("muted"))}>
+                                    <span class="watch-kind">{ self.t(Text::RuleKindName(rule.kind)) }</span>
+                                    <span class="watch-value">{ rule.describe(self.locale) }</span>
+                                    <button
+                                        title={self.t(if rule.muted { Text::Unmute } else { Text::Mute })}
+                                        aria-pressed={rule.muted.to_string()}
+                                        onclick={ctx.link().callback(move |_| Msg::MuteWatchRule(i))}
+                                    >
+                                        <i class="material-symbols-outlined" aria-hidden="true">
+                                            { if rule.muted { "notifications_off" } else { "notifications_active" } }
+                                        </i>
+                                    </button>
+                                    <button title={self.t(Text::Remove)} onclick={ctx.link().callback(move |_| Msg::RemoveWatchRule(i))}>
+                                        <i class="material-symbols-outlined" aria-hidden="true">{"delete"}</i>
+                                    </button>
+                                </li>
+                            })
+                        }
+                    </ul>
+                    <div class="watch-form">
+                        <select
+                            aria-label={self.t(Text::RuleKind)}
+                            onchange={ctx.link().callback(|e: Event| {
+                                Msg::SetWatchKind(RuleKind::parse(&e.target_unchecked_into::<HtmlSelectElement>().value()))
+                            })}
+                        >
+                            {
+                                for RuleKind::ALL.into_iter().map(|kind| html! {
+                                    <option value={kind.as_str()} selected={kind == draft.kind}>{ self.t(Text::RuleKindName(kind)) }</option>
+                                })
+                            }
+                        </select>
+                        {value_input}
+                        <button
+                            disabled={draft.rule().is_none()}
+                            onclick={ctx.link().callback(|_| Msg::AddWatchRule)}
+                        >
+                            { self.t(Text::Add) }
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+}
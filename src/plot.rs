@@ -0,0 +1,682 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlotKind {
+    #[default]
+    Line,
+    Scatter,
+    Histogram,
+}
+
+// What to draw, given either by a ```plot block or by the chart controls
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlotOptions {
+    pub kind: PlotKind,
+    // Column of the x axis; the row number if not given
+    pub x: Option<String>,
+    // Columns of the y axis; all the other numeric columns if empty
+    pub y: Vec<String>,
+    pub logx: bool,
+    pub logy: bool,
+    pub bins: Option<usize>,
+    pub title: Option<String>,
+}
+
+impl PlotOptions {
+    // Parse the body of a ```plot block written as `key: value` lines
+    // Returns the attachment reference (`%3`, `%{name.csv}`) and the options
+    pub fn parse_block(body: &str) -> Result<(String, Self), String> {
+        let mut source = None;
+        let mut options = Self::default();
+
+        for line in body.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if line.starts_with('%') {
+                source = Some(line.to_string());
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("expected `key: value`, found `{}`", line))?;
+            let value = value.trim();
+            match key.trim() {
+                "data" => source = Some(value.to_string()),
+                "type" | "kind" => {
+                    options.kind = match value {
+                        "line" => PlotKind::Line,
+                        "scatter" => PlotKind::Scatter,
+                        "histogram" | "hist" => PlotKind::Histogram,
+                        _ => return Err(format!("unknown plot type `{}`", value)),
+                    }
+                }
+                "x" => options.x = Some(value.to_string()),
+                "y" => {
+                    options.y = value
+                        .split(',')
+                        .map(|column| column.trim().to_string())
+                        .filter(|column| !column.is_empty())
+                        .collect()
+                }
+                "logx" => options.logx = parse_flag(value)?,
+                "logy" => options.logy = parse_flag(value)?,
+                "bins" => {
+                    options.bins = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid bins `{}`", value))?,
+                    )
+                }
+                "title" => options.title = Some(value.to_string()),
+                key => return Err(format!("unknown plot option `{}`", key)),
+            }
+        }
+
+        source
+            .map(|source| (source, options))
+            .ok_or_else(|| "no data given, e.g. `data: %1`".to_string())
+    }
+}
+
+fn parse_flag(value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("expected true or false, found `{}`", value)),
+    }
+}
+
+// Numeric columns of a delimited text
+struct Table {
+    names: Vec<String>,
+    columns: Vec<Vec<f64>>,
+}
+
+impl Table {
+    fn parse(text: &str, delimiter: &str) -> Result<Self, String> {
+        let delimiter = if delimiter == "tab" { b'\t' } else { b',' };
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+
+        let mut rows = reader.records();
+        let first = match rows.next() {
+            Some(row) => row.map_err(|err| err.to_string())?,
+            None => return Err("no data".to_string()),
+        };
+
+        // Without a header, the columns are simply numbered
+        // Empty cells are missing values, not names
+        let has_header = first
+            .iter()
+            .any(|cell| !cell.is_empty() && cell.parse::<f64>().is_err());
+        let width = first.len();
+        let mut names: Vec<String> = if has_header {
+            first.iter().map(str::to_string).collect()
+        } else {
+            (1..=width).map(|i| format!("column {}", i)).collect()
+        };
+        let mut columns: Vec<Vec<f64>> = vec![vec![]; width];
+        let mut numeric = vec![true; width];
+
+        let mut push_row = |row: &csv::StringRecord| {
+            for (i, cell) in row.iter().take(width).enumerate() {
+                match cell.parse::<f64>() {
+                    Ok(value) => columns[i].push(value),
+                    Err(_) if cell.is_empty() => columns[i].push(f64::NAN),
+                    Err(_) => numeric[i] = false,
+                }
+            }
+            for column in columns.iter_mut().skip(row.len()) {
+                column.push(f64::NAN);
+            }
+        };
+        if !has_header {
+            push_row(&first);
+        }
+        for row in rows {
+            push_row(&row.map_err(|err| err.to_string())?);
+        }
+
+        // Keep only the numeric ones
+        let mut keep = numeric.iter();
+        names.retain(|_| *keep.next().unwrap());
+        let mut keep = numeric.iter();
+        columns.retain(|_| *keep.next().unwrap());
+
+        if names.is_empty() {
+            return Err("no numeric columns".to_string());
+        }
+        Ok(Self { names, columns })
+    }
+
+    fn column(&self, name: &str) -> Result<&[f64], String> {
+        self.names
+            .iter()
+            .position(|n| n == name)
+            .map(|i| self.columns[i].as_slice())
+            .ok_or_else(|| format!("no numeric column `{}`", name))
+    }
+}
+
+const PALETTE: [&str; 6] = [
+    "#4a90e2", "#e2564a", "#50b848", "#f5a623", "#9b59b6", "#1abc9c",
+];
+
+// Colors around the data, following the theme of the page (see. styles.css)
+// A canvas outside the page, e.g. for a report, keeps the light ones
+struct ChartColors {
+    background: String,
+    grid: String,
+    text: String,
+    axis: String,
+}
+
+impl ChartColors {
+    fn of(canvas: &HtmlCanvasElement) -> Self {
+        let style =
+            web_sys::window().and_then(|window| window.get_computed_style(canvas).ok().flatten());
+        let color = |name: &str, default: &str| {
+            style
+                .as_ref()
+                .and_then(|style| style.get_property_value(name).ok())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .unwrap_or_else(|| default.to_string())
+        };
+        Self {
+            background: color("--plot-background", "#ffffff"),
+            grid: color("--plot-grid", "#e6e6e6"),
+            text: color("--plot-text", "#555555"),
+            axis: color("--plot-axis", "#333333"),
+        }
+    }
+}
+
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 24.0;
+const MARGIN_BOTTOM: f64 = 36.0;
+
+// An axis mapping data values to pixels
+struct Axis {
+    min: f64,
+    max: f64,
+    log: bool,
+    from: f64,
+    to: f64,
+}
+
+impl Axis {
+    fn new(values: impl Iterator<Item = f64>, log: bool, from: f64, to: f64) -> Self {
+        let (mut min, mut max) = values
+            .filter_map(|v| transform(v, log))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            });
+        if !min.is_finite() {
+            min = 0.0;
+            max = 1.0;
+        } else if min == max {
+            let pad = if min == 0.0 { 1.0 } else { min.abs() * 0.1 };
+            min -= pad;
+            max += pad;
+        }
+        Self {
+            min,
+            max,
+            log,
+            from,
+            to,
+        }
+    }
+
+    // Pixel position of a (transformed) value
+    fn pixel(&self, value: f64) -> f64 {
+        self.from + (value - self.min) / (self.max - self.min) * (self.to - self.from)
+    }
+
+    fn ticks(&self) -> Vec<(f64, String)> {
+        if self.log && self.max - self.min >= 1.0 {
+            let step = ((self.max - self.min) / 6.0).ceil().max(1.0);
+            let mut ticks = vec![];
+            let mut decade = (self.min / step).ceil() * step;
+            while decade <= self.max {
+                ticks.push((decade, format!("1e{}", decade)));
+                decade += step;
+            }
+            return ticks;
+        }
+        nice_ticks(self.min, self.max, 6)
+            .into_iter()
+            .map(|v| {
+                let label = if self.log {
+                    format_number(10f64.powf(v))
+                } else {
+                    format_number(v)
+                };
+                (v, label)
+            })
+            .collect()
+    }
+}
+
+fn transform(value: f64, log: bool) -> Option<f64> {
+    match (value.is_finite(), log) {
+        (false, _) => None,
+        (true, true) if value <= 0.0 => None,
+        (true, true) => Some(value.log10()),
+        (true, false) => Some(value),
+    }
+}
+
+fn nice_ticks(min: f64, max: f64, count: usize) -> Vec<f64> {
+    let raw = (max - min) / count as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = match raw / magnitude {
+        n if n < 1.5 => 1.0,
+        n if n < 3.0 => 2.0,
+        n if n < 7.0 => 5.0,
+        _ => 10.0,
+    } * magnitude;
+    if !(step.is_finite() && step > 0.0) {
+        return vec![];
+    }
+
+    // By the index, as adding a step below the float resolution would not move the value
+    let first = (min / step).ceil() * step;
+    (0..)
+        .map(|i| first + i as f64 * step)
+        .take_while(|value| *value <= max + step * 1e-9)
+        // Avoid "-0" and rounding noise like 5.551115123125783e-17 in place of 0
+        .map(|value| {
+            if value.abs() < step * 1e-9 {
+                0.0
+            } else {
+                value
+            }
+        })
+        .collect()
+}
+
+fn format_number(value: f64) -> String {
+    let abs = value.abs();
+    if abs != 0.0 && !(1e-3..1e5).contains(&abs) {
+        format!("{:.1e}", value)
+    } else {
+        let text = format!("{:.4}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+// Draw a chart of the delimited text on the canvas
+fn draw(
+    canvas: &HtmlCanvasElement,
+    text: &str,
+    delimiter: &str,
+    options: &PlotOptions,
+) -> Result<(), String> {
+    let table = Table::parse(text, delimiter)?;
+    let ctx = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or("canvas is not available")?;
+
+    // Work in CSS pixels on high-DPI screens
+    let width = canvas.client_width().max(1) as f64;
+    let height = canvas.client_height().max(1) as f64;
+    let ratio = canvas.width() as f64 / width;
+    ctx.set_transform(ratio, 0.0, 0.0, ratio, 0.0, 0.0)
+        .map_err(|_| "cannot scale the canvas")?;
+    let colors = ChartColors::of(canvas);
+    ctx.set_fill_style_str(&colors.background);
+    ctx.fill_rect(0.0, 0.0, width, height);
+
+    // The series to draw
+    let y_names: Vec<&str> = if options.y.is_empty() {
+        table
+            .names
+            .iter()
+            .map(String::as_str)
+            .filter(|name| Some(*name) != options.x.as_deref())
+            .collect()
+    } else {
+        options.y.iter().map(String::as_str).collect()
+    };
+    if y_names.is_empty() {
+        return Err("no column to plot".to_string());
+    }
+    let ys = y_names
+        .iter()
+        .map(|name| table.column(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let index: Vec<f64> = (0..table.columns[0].len()).map(|i| i as f64).collect();
+    let xs = match options.x.as_deref() {
+        Some(name) if !name.is_empty() => table.column(name)?,
+        _ => index.as_slice(),
+    };
+
+    let plot_left = MARGIN_LEFT;
+    let plot_right = width - MARGIN_RIGHT;
+    let plot_top = MARGIN_TOP;
+    let plot_bottom = height - MARGIN_BOTTOM;
+
+    // Axes, and the (transformed) points of each series
+    let (x_axis, y_axis, series) = match options.kind {
+        PlotKind::Histogram => {
+            let x_axis = Axis::new(
+                ys.iter().flat_map(|y| y.iter().copied()),
+                options.logx,
+                plot_left,
+                plot_right,
+            );
+            let bins = options.bins.unwrap_or(20).clamp(1, 1000);
+            let bin_width = (x_axis.max - x_axis.min) / bins as f64;
+            let counts: Vec<Vec<f64>> = ys
+                .iter()
+                .map(|y| {
+                    let mut counts = vec![0.0; bins];
+                    for v in y.iter().filter_map(|v| transform(*v, options.logx)) {
+                        let bin = ((v - x_axis.min) / bin_width) as usize;
+                        counts[bin.min(bins - 1)] += 1.0;
+                    }
+                    counts
+                })
+                .collect();
+            let y_axis = Axis::new(
+                counts.iter().flatten().copied().chain([1.0]),
+                options.logy,
+                plot_bottom,
+                plot_top,
+            );
+            let series = counts
+                .into_iter()
+                .map(|counts| {
+                    counts
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, count)| (x_axis.min + bin_width * i as f64, count))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            (x_axis, y_axis, series)
+        }
+        PlotKind::Line | PlotKind::Scatter => {
+            let x_axis = Axis::new(xs.iter().copied(), options.logx, plot_left, plot_right);
+            let y_axis = Axis::new(
+                ys.iter().flat_map(|y| y.iter().copied()),
+                options.logy,
+                plot_bottom,
+                plot_top,
+            );
+            let series = ys
+                .iter()
+                .map(|y| {
+                    xs.iter()
+                        .zip(y.iter())
+                        .filter_map(|(x, y)| {
+                            Some((transform(*x, options.logx)?, transform(*y, options.logy)?))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            (x_axis, y_axis, series)
+        }
+    };
+
+    // Grid and tick labels
+    ctx.set_line_width(1.0);
+    ctx.set_font("11px sans-serif");
+    ctx.set_stroke_style_str(&colors.grid);
+    ctx.set_fill_style_str(&colors.text);
+    ctx.set_text_align("center");
+    ctx.set_text_baseline("top");
+    for (value, label) in x_axis.ticks() {
+        let x = x_axis.pixel(value);
+        ctx.begin_path();
+        ctx.move_to(x, plot_top);
+        ctx.line_to(x, plot_bottom);
+        ctx.stroke();
+        let _ = ctx.fill_text(&label, x, plot_bottom + 4.0);
+    }
+    ctx.set_text_align("right");
+    ctx.set_text_baseline("middle");
+    for (value, label) in y_axis.ticks() {
+        let y = y_axis.pixel(value);
+        ctx.begin_path();
+        ctx.move_to(plot_left, y);
+        ctx.line_to(plot_right, y);
+        ctx.stroke();
+        let _ = ctx.fill_text(&label, plot_left - 4.0, y);
+    }
+    ctx.set_stroke_style_str(&colors.axis);
+    ctx.stroke_rect(
+        plot_left,
+        plot_top,
+        plot_right - plot_left,
+        plot_bottom - plot_top,
+    );
+
+    // Axis titles
+    ctx.set_text_align("center");
+    ctx.set_text_baseline("bottom");
+    let x_title = match options.kind {
+        PlotKind::Histogram => y_names.join(", "),
+        _ => options
+            .x
+            .clone()
+            .filter(|x| !x.is_empty())
+            .unwrap_or_else(|| "row".to_string()),
+    };
+    let _ = ctx.fill_text(&x_title, (plot_left + plot_right) / 2.0, height - 2.0);
+    if let Some(title) = &options.title {
+        ctx.set_font("bold 12px sans-serif");
+        ctx.set_text_baseline("top");
+        let _ = ctx.fill_text(title, (plot_left + plot_right) / 2.0, 4.0);
+        ctx.set_font("11px sans-serif");
+    }
+
+    // Clip the data to the plot area
+    ctx.save();
+    ctx.begin_path();
+    ctx.rect(
+        plot_left,
+        plot_top,
+        plot_right - plot_left,
+        plot_bottom - plot_top,
+    );
+    ctx.clip();
+    for (i, points) in series.iter().enumerate() {
+        let color = PALETTE[i % PALETTE.len()];
+        ctx.set_stroke_style_str(color);
+        ctx.set_fill_style_str(color);
+        match options.kind {
+            PlotKind::Line => {
+                ctx.set_line_width(1.5);
+                ctx.begin_path();
+                for (j, (x, y)) in points.iter().enumerate() {
+                    if j == 0 {
+                        ctx.move_to(x_axis.pixel(*x), y_axis.pixel(*y));
+                    } else {
+                        ctx.line_to(x_axis.pixel(*x), y_axis.pixel(*y));
+                    }
+                }
+                ctx.stroke();
+            }
+            PlotKind::Scatter => {
+                for (x, y) in points {
+                    ctx.begin_path();
+                    let _ = ctx.arc(
+                        x_axis.pixel(*x),
+                        y_axis.pixel(*y),
+                        2.5,
+                        0.0,
+                        std::f64::consts::TAU,
+                    );
+                    ctx.fill();
+                }
+            }
+            PlotKind::Histogram => {
+                let bins = points.len() as f64;
+                let bar = (x_axis.max - x_axis.min) / bins;
+                ctx.set_global_alpha(if series.len() > 1 { 0.5 } else { 0.8 });
+                let base = y_axis.pixel(y_axis.min);
+                for (x, count) in points {
+                    let Some(count) = transform(*count, options.logy) else {
+                        continue;
+                    };
+                    let left = x_axis.pixel(*x);
+                    let right = x_axis.pixel(x + bar);
+                    let top = y_axis.pixel(count);
+                    ctx.fill_rect(left, top, (right - left - 1.0).max(1.0), base - top);
+                }
+                ctx.set_global_alpha(1.0);
+            }
+        }
+    }
+    ctx.restore();
+
+    // Legend
+    if series.len() > 1 || options.kind != PlotKind::Histogram {
+        ctx.set_text_align("left");
+        ctx.set_text_baseline("middle");
+        let mut x = plot_left + 8.0;
+        let y = plot_top + 10.0;
+        for (i, name) in y_names.iter().enumerate() {
+            ctx.set_fill_style_str(PALETTE[i % PALETTE.len()]);
+            ctx.fill_rect(x, y - 4.0, 10.0, 8.0);
+            ctx.set_fill_style_str(&colors.axis);
+            let _ = ctx.fill_text(name, x + 14.0, y);
+            let text_width = ctx.measure_text(name).map(|m| m.width()).unwrap_or(50.0);
+            x += text_width + 28.0;
+        }
+    }
+
+    Ok(())
+}
+
+// Interface to Java Script
+//   plot_columns(text, delimiter) -> [numeric column names]
+//   draw_plot(canvas, text, delimiter, options) -> null, or an error message
+pub fn register_plot_callbacks() {
+    let columns = Closure::wrap(Box::new(move |text: JsValue, delimiter: JsValue| {
+        let text = text.as_string().unwrap_or_default();
+        let delimiter = delimiter.as_string().unwrap_or_default();
+        match Table::parse(&text, &delimiter) {
+            Ok(table) => table
+                .names
+                .iter()
+                .map(|name| JsValue::from_str(name))
+                .collect::<js_sys::Array>()
+                .into(),
+            Err(_) => js_sys::Array::new().into(),
+        }
+    }) as Box<dyn Fn(JsValue, JsValue) -> JsValue>);
+
+    let draw_plot = Closure::wrap(Box::new(
+        move |canvas: JsValue, text: JsValue, delimiter: JsValue, options: JsValue| {
+            let result = canvas
+                .dyn_into::<HtmlCanvasElement>()
+                .map_err(|_| "not a canvas".to_string())
+                .and_then(|canvas| {
+                    let options: PlotOptions =
+                        serde_json::from_str(&options.as_string().unwrap_or_default())
+                            .map_err(|err| err.to_string())?;
+                    draw(
+                        &canvas,
+                        &text.as_string().unwrap_or_default(),
+                        &delimiter.as_string().unwrap_or_default(),
+                        &options,
+                    )
+                });
+            match result {
+                Ok(()) => JsValue::NULL,
+                Err(err) => JsValue::from_str(&err),
+            }
+        },
+    )
+        as Box<dyn Fn(JsValue, JsValue, JsValue, JsValue) -> JsValue>);
+
+    let global = js_sys::global();
+    js_sys::Reflect::set(
+        &global,
+        &JsValue::from_str("plot_columns"),
+        columns.as_ref().unchecked_ref(),
+    )
+    .expect("Failed to register `plot_columns`");
+    js_sys::Reflect::set(
+        &global,
+        &JsValue::from_str("draw_plot"),
+        draw_plot.as_ref().unchecked_ref(),
+    )
+    .expect("Failed to register `draw_plot`");
+
+    columns.forget();
+    draw_plot.forget();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Equal up to the rounding noise, which the labels hide
+    fn assert_ticks(ticks: Vec<f64>, expected: &[f64]) {
+        assert_eq!(ticks.len(), expected.len(), "{:?}", ticks);
+        for (tick, expected) in ticks.iter().zip(expected) {
+            assert!((tick - expected).abs() < 1e-9, "{:?}", ticks);
+        }
+    }
+
+    #[test]
+    fn ticks_at_round_steps() {
+        assert_ticks(nice_ticks(0.0, 10.0, 6), &[0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+        assert_ticks(nice_ticks(-1.0, 1.0, 6), &[-1.0, -0.5, 0.0, 0.5, 1.0]);
+        assert_ticks(
+            nice_ticks(0.1, 0.7, 6),
+            &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7],
+        );
+        // Not "-0"
+        assert!(nice_ticks(-1.0, 1.0, 6)[2].is_sign_positive());
+    }
+
+    #[test]
+    fn ticks_of_a_range_below_the_float_resolution() {
+        let ticks = nice_ticks(0.3, 0.1 + 0.2, 6);
+        assert!(!ticks.is_empty() && ticks.len() < 10);
+        assert!(nice_ticks(1.0, 1.0, 6).is_empty());
+    }
+
+    #[test]
+    fn table_with_header() {
+        let table = Table::parse("time, value, note\n1, 2.5, ok\n2, , bad\n3, 4", "comma").unwrap();
+        assert_eq!(table.names, ["time", "value"]);
+        assert_eq!(table.column("time").unwrap(), [1.0, 2.0, 3.0]);
+        let value = table.column("value").unwrap();
+        assert_eq!(value[0], 2.5);
+        assert!(value[1].is_nan());
+        assert_eq!(value[2], 4.0);
+        assert!(table.column("note").is_err());
+    }
+
+    #[test]
+    fn table_without_header() {
+        // An empty first cell is a missing value, not a name
+        let table = Table::parse("1\t\n2\t3", "tab").unwrap();
+        assert_eq!(table.names, ["column 1", "column 2"]);
+        assert_eq!(table.column("column 1").unwrap(), [1.0, 2.0]);
+        assert!(table.column("column 2").unwrap()[0].is_nan());
+    }
+
+    #[test]
+    fn table_without_numbers() {
+        assert!(Table::parse("", "comma").is_err());
+        assert!(Table::parse("name\nvalue", "comma").is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::Msg;
+
+// Font sizes offered, in px
+const FONT_SIZES: [u32; 6] = [12, 13, 14, 16, 18, 20];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    // Following prefers-color-scheme
+    #[default]
+    System,
+    Light,
+    Dark,
+    HighContrast,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [
+        Theme::System,
+        Theme::Light,
+        Theme::Dark,
+        Theme::HighContrast,
+    ];
+
+    // As set to data-theme of the page (see. styles.css)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::HighContrast => "high-contrast",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Density {
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+impl Density {
+    pub const ALL: [Density; 2] = [Density::Comfortable, Density::Compact];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Density::Comfortable => "comfortable",
+            Density::Compact => "compact",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreferenceField {
+    Theme,
+    Density,
+    FontSize,
+}
+
+// Display settings of this browser
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub theme: Theme,
+    pub density: Density,
+    pub font_size: u32,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            density: Density::default(),
+            font_size: 16,
+        }
+    }
+}
+
+// Also read by index.html, to apply them before the page is drawn
+const PREFERENCES_KEY: &str = "elogbook.preferences";
+
+impl Preferences {
+    pub fn load() -> Self {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(PREFERENCES_KEY).ok().flatten())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Some(storage) =
+            web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+        {
+            let json = serde_json::to_string(self).unwrap_or_default();
+            let _ = storage.set_item(PREFERENCES_KEY, &json);
+        }
+    }
+
+    pub fn set(&mut self, field: PreferenceField, value: &str) {
+        match field {
+            PreferenceField::Theme => {
+                if let Some(theme) = Theme::ALL.into_iter().find(|t| t.as_str() == value) {
+                    self.theme = theme;
+                }
+            }
+            PreferenceField::Density => {
+                if let Some(density) = Density::ALL.into_iter().find(|d| d.as_str() == value) {
+                    self.density = density;
+                }
+            }
+            PreferenceField::FontSize => {
+                if let Ok(size) = value.parse::<u32>() {
+                    self.font_size = size;
+                }
+            }
+        }
+    }
+
+    // Set them to the root element, the styles follow (see. styles.css)
+    pub fn apply(&self) {
+        let Some(root) = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.document_element())
+        else {
+            return;
+        };
+        let _ = root.set_attribute("data-theme", self.theme.as_str());
+        let _ = root.set_attribute("data-density", self.density.as_str());
+        if let Some(root) = wasm_bindgen::JsCast::dyn_ref::<web_sys::HtmlElement>(&root) {
+            let _ = root
+                .style()
+                .set_property("--font-size", &format!("{}px", self.font_size));
+        }
+    }
+}
+
+impl Model {
+    // Dialog of the display settings
+    pub fn preferences_dialog(&self, ctx: &Context<Self>) -> Html {
+        if !self.preferences_open {
+            return html! {};
+        }
+        let preferences = &self.preferences;
+        let onchange = |field: PreferenceField| {
+            ctx.link().callback(move |e: Event| {
+                Msg::UpdatePreferences(
+                    field,
+                    e.target_unchecked_into::<HtmlSelectElement>().value(),
+                )
+            })
+        };
+
+        html! {
+            <div class="dialog-backdrop">
+                <div class="dialog preferences-dialog" role="dialog" aria-modal="true" aria-label={self.t(Text::Display)}>
+                    <div class="dialog-header">
+                        <span>{ self.t(Text::Display) }</span>
+                        <button class="close-button" aria-label={self.t(Text::Close)} onclick={ctx.link().callback(|_| Msg::OpenPreferences(false))}>
+                            {"×"}
+                        </button>
+                    </div>
+                    <label>
+                        <span>{ self.t(Text::Theme) }</span>
+                        <select onchange={onchange(PreferenceField::Theme)}>
+                            {
+                                for Theme::ALL.into_iter().map(|theme| html! {
+                                    <option value={theme.as_str()} selected={theme == preferences.theme}>
+                                        { self.t(Text::ThemeName(theme)) }
+                                    </option>
+                                })
+                            }
+                        </select>
+                    </label>
+                    <label>
+                        <span>{ self.t(Text::Density) }</span>
+                        <select onchange={onchange(PreferenceField::Density)}>
+                            {
+                                for Density::ALL.into_iter().map(|density| html! {
+                                    <option value={density.as_str()} selected={density == preferences.density}>
+                                        { self.t(Text::DensityName(density)) }
+                                    </option>
+                                })
+                            }
+                        </select>
+                    </label>
+                    <label>
+                        <span>{ self.t(Text::FontSize) }</span>
+                        <select onchange={onchange(PreferenceField::FontSize)}>
+                            {
+                                for FONT_SIZES.into_iter().map(|size| html! {
+                                    <option value={size.to_string()} selected={size == preferences.font_size}>
+                                        { format!("{}px", size) }
+                                    </option>
+                                })
+                            }
+                        </select>
+                    </label>
+                </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::models::*;
+
+// How an attachment is previewed inline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreviewKind {
+    Image,
+    Svg,
+    Audio,
+    Video,
+    Pdf,
+    Text,
+    // Delimited data shown as a sortable table: "comma" or "tab"
+    Table(&'static str),
+    // Structured data shown as a collapsible tree: "json", "yaml" or "xml"
+    Tree(&'static str),
+    // Source file with the highlight.js language name
+    Code(&'static str),
+    None,
+}
+
+impl PreviewKind {
+    // Chosen by the MIME type, falling back to the file extension
+    // when the MIME type is missing or too generic to tell
+    pub fn of(attachment: &Attachment) -> Self {
+        match Self::from_mime(&attachment.mime) {
+            PreviewKind::None | PreviewKind::Text => {
+                match Self::from_extension(&attachment.original_name) {
+                    PreviewKind::None => Self::from_mime(&attachment.mime),
+                    kind => kind,
+                }
+            }
+            kind => kind,
+        }
+    }
+
+    fn from_mime(mime: &str) -> Self {
+        // Ignore the parameters, e.g. "; charset=utf-8"
+        let mime = mime.split(';').next().unwrap_or("").trim();
+        match mime {
+            "image/png" | "image/jpeg" | "image/gif" | "image/webp" => PreviewKind::Image,
+            "image/svg+xml" => PreviewKind::Svg,
+            "application/pdf" => PreviewKind::Pdf,
+            "text/csv" => PreviewKind::Table("comma"),
+            "text/tab-separated-values" => PreviewKind::Table("tab"),
+            "application/json" => PreviewKind::Tree("json"),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                PreviewKind::Tree("yaml")
+            }
+            "application/xml" | "text/xml" => PreviewKind::Tree("xml"),
+            "text/x-python" => PreviewKind::Code("python"),
+            "text/x-c" | "text/x-csrc" => PreviewKind::Code("c"),
+            "text/x-c++src" => PreviewKind::Code("cpp"),
+            "text/x-rust" => PreviewKind::Code("rust"),
+            "text/javascript" | "application/javascript" => PreviewKind::Code("javascript"),
+            "application/x-sh" | "text/x-sh" => PreviewKind::Code("bash"),
+            "text/plain" => PreviewKind::Text,
+            _ if mime.starts_with("audio/") => PreviewKind::Audio,
+            _ if mime.starts_with("video/") => PreviewKind::Video,
+            _ if mime.ends_with("+json") => PreviewKind::Tree("json"),
+            _ if mime.ends_with("+xml") => PreviewKind::Tree("xml"),
+            _ => PreviewKind::None,
+        }
+    }
+
+    fn from_extension(name: &str) -> Self {
+        let extension = match name.rsplit_once('.') {
+            Some((_, extension)) => extension.to_ascii_lowercase(),
+            None => return PreviewKind::None,
+        };
+        match extension.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "webp" => PreviewKind::Image,
+            "svg" => PreviewKind::Svg,
+            "mp3" | "wav" | "ogg" | "oga" | "flac" | "m4a" => PreviewKind::Audio,
+            "mp4" | "webm" | "ogv" | "mov" => PreviewKind::Video,
+            "pdf" => PreviewKind::Pdf,
+            "csv" => PreviewKind::Table("comma"),
+            "tsv" => PreviewKind::Table("tab"),
+            "json" => PreviewKind::Tree("json"),
+            "yaml" | "yml" => PreviewKind::Tree("yaml"),
+            "xml" => PreviewKind::Tree("xml"),
+            "txt" | "log" | "md" => PreviewKind::Text,
+            "rs" => PreviewKind::Code("rust"),
+            "py" => PreviewKind::Code("python"),
+            "js" | "mjs" => PreviewKind::Code("javascript"),
+            "ts" => PreviewKind::Code("typescript"),
+            "c" | "h" => PreviewKind::Code("c"),
+            "cpp" | "cc" | "cxx" | "hpp" | "hh" => PreviewKind::Code("cpp"),
+            "java" => PreviewKind::Code("java"),
+            "go" => PreviewKind::Code("go"),
+            "rb" => PreviewKind::Code("ruby"),
+            "sh" | "bash" => PreviewKind::Code("bash"),
+            "jl" => PreviewKind::Code("julia"),
+            "m" => PreviewKind::Code("matlab"),
+            "r" => PreviewKind::Code("r"),
+            "sql" => PreviewKind::Code("sql"),
+            "tex" => PreviewKind::Code("latex"),
+            "toml" | "ini" | "cfg" => PreviewKind::Code("ini"),
+            "css" => PreviewKind::Code("css"),
+            "html" | "htm" => PreviewKind::Code("xml"),
+            "cmake" => PreviewKind::Code("cmake"),
+            _ => PreviewKind::None,
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use crate::models::*;
+use crate::plot::*;
+
+// How an attachment is pointed at from the log text
+#[derive(Debug, PartialEq)]
+pub enum RefTarget {
+    Id(u32),
+    Name(String),
+}
+
+// Rendering options given to a reference, e.g. `%{3 width=50% page=2}`
+#[derive(Debug, Default, PartialEq)]
+pub struct RefOptions {
+    pub width: Option<String>,
+    pub thumbnail: bool,
+    pub link_only: bool,
+    pub plot: bool,
+    pub page: Option<u32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AttachmentRef {
+    pub target: RefTarget,
+    pub options: RefOptions,
+}
+
+impl AttachmentRef {
+    // Parse the inside of `%{...}`
+    // The first token is the target (a number, a bare file name or a "quoted name"),
+    // and the rest are options: `width=<len>`, `page=<n>`, `thumb`, `link` and `plot`.
+    pub fn parse(body: &str) -> Result<Self, String> {
+        let body = body.trim();
+        if body.is_empty() {
+            return Err("empty reference".to_string());
+        }
+
+        // The target
+        let (target, rest) = if let Some(quoted) = body.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (
+                    RefTarget::Name(quoted[..end].to_string()),
+                    &quoted[end + 1..],
+                ),
+                None => return Err("unterminated quoted name".to_string()),
+            }
+        } else {
+            let end = body.find(char::is_whitespace).unwrap_or(body.len());
+            let token = &body[..end];
+            let target = match token.parse::<u32>() {
+                Ok(id) => RefTarget::Id(id),
+                Err(_) => RefTarget::Name(token.to_string()),
+            };
+            (target, &body[end..])
+        };
+
+        // The options
+        let mut options = RefOptions::default();
+        for token in rest.split_whitespace() {
+            match token.split_once('=') {
+                Some(("width", value)) => options.width = Some(parse_length(value)?),
+                Some(("page", value)) => match value.parse::<u32>() {
+                    Ok(page) if page > 0 => options.page = Some(page),
+                    _ => return Err(format!("invalid page `{}`", value)),
+                },
+                None if token == "thumb" || token == "thumbnail" => options.thumbnail = true,
+                None if token == "link" => options.link_only = true,
+                None if token == "plot" => options.plot = true,
+                _ => return Err(format!("unknown option `{}`", token)),
+            }
+        }
+
+        Ok(Self { target, options })
+    }
+
+    // Find the attachment this reference points at
+    pub fn resolve<'a>(&self, attachments: &'a [Attachment]) -> Result<&'a Attachment, String> {
+        match &self.target {
+            RefTarget::Id(id) => attachments
+                .iter()
+                .find(|att| att.id == *id)
+                .ok_or_else(|| format!("no attachment %{}", id)),
+            RefTarget::Name(name) => attachments
+                .iter()
+                .find(|att| &att.original_name == name)
+                .ok_or_else(|| format!("no attachment named `{}`", name)),
+        }
+    }
+}
+
+// Only plain CSS lengths are accepted as they end up in a style attribute
+fn parse_length(value: &str) -> Result<String, String> {
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%');
+    let unit = &value[digits.len()..];
+    let is_number = !digits.is_empty() && digits.parse::<f64>().is_ok();
+    match unit {
+        "" if is_number => Ok(format!("{}px", digits)),
+        "px" | "%" | "em" | "rem" | "vw" if is_number => Ok(value.to_string()),
+        _ => Err(format!("invalid width `{}`", value)),
+    }
+}
+
+// A piece of the log text, as split by `split_references`
+pub enum Piece<'a> {
+    Text(String),
+    // Inside of `[[entry:...]]`
+    EntryLink(String),
+    // `%N` or `%{...}` as written, and the attachment it points at
+    Attachment(String, Result<(&'a Attachment, RefOptions), String>),
+    // A ```plot block, and the attachment it plots
+    Plot(Result<(&'a Attachment, PlotOptions), String>),
+}
+
+// Split the log text into the plain text and the references
+// Code blocks and spans are left as written, e.g. `printf("%5d")`, but ```plot blocks
+pub fn split_references<'a>(log_text: &str, attachments: &'a [Attachment]) -> Vec<Piece<'a>> {
+    let mut pieces = vec![];
+    for (part, segment) in split_code(log_text) {
+        match segment {
+            Segment::Text => scan_text(part, attachments, &mut pieces),
+            Segment::Block => match plot_block_body(part) {
+                Some(body) => pieces.push(Piece::Plot(resolve_plot_block(body, attachments))),
+                None => pieces.push(Piece::Text(part.to_string())),
+            },
+            Segment::Span => pieces.push(Piece::Text(part.to_string())),
+        }
+    }
+    pieces
+}
+
+fn resolve_plot_block<'a>(
+    body: &str,
+    attachments: &'a [Attachment],
+) -> Result<(&'a Attachment, PlotOptions), String> {
+    let (source, options) = PlotOptions::parse_block(body)?;
+    let reference = match source.strip_prefix("%{").and_then(|s| s.strip_suffix('}')) {
+        Some(braced) => AttachmentRef::parse(braced)?,
+        None => AttachmentRef::parse(source.trim_start_matches('%'))?,
+    };
+    Ok((reference.resolve(attachments)?, options))
+}
+
+// Part of the log text, as split by `split_code`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment {
+    Text,
+    // Fenced with ``` or ~~~, from the opening line to the closing one
+    Block,
+    // Inline, in backticks
+    Span,
+}
+
+// Split the text into the code (fenced blocks and inline spans) and the rest
+fn split_code(text: &str) -> Vec<(&str, Segment)> {
+    let mut parts = vec![];
+    let bytes = text.as_bytes();
+    // Start of the text not yet taken
+    let mut start = 0;
+    let mut i = 0;
+    let mut line_start = true;
+
+    while i < text.len() {
+        if line_start {
+            let line_end = text[i..].find('\n').map_or(text.len(), |end| i + end + 1);
+            let line = text[i..line_end].trim_start();
+            if let Some(fence) = ["```", "~~~"].into_iter().find(|f| line.starts_with(f)) {
+                // Up to the closing fence line, or to the end
+                let end = match find_fence(&text[line_end..], fence) {
+                    Some(close) => {
+                        let close = line_end + close;
+                        text[close..]
+                            .find('\n')
+                            .map_or(text.len(), |end| close + end + 1)
+                    }
+                    None => text.len(),
+                };
+                parts.push((&text[start..i], Segment::Text));
+                parts.push((&text[i..end], Segment::Block));
+                start = end;
+                i = end;
+                continue;
+            }
+        }
+
+        if bytes[i] == b'`' {
+            // A span closes with a run of as many backticks
+            let run = text[i..].bytes().take_while(|b| *b == b'`').count();
+            let mut search = i + run;
+            let mut end = None;
+            while let Some(found) = text[search..].find('`') {
+                let at = search + found;
+                let len = text[at..].bytes().take_while(|b| *b == b'`').count();
+                if len == run {
+                    end = Some(at + len);
+                    break;
+                }
+                search = at + len;
+            }
+            match end {
+                Some(end) => {
+                    parts.push((&text[start..i], Segment::Text));
+                    parts.push((&text[i..end], Segment::Span));
+                    start = end;
+                    i = end;
+                }
+                None => i += run,
+            }
+            line_start = false;
+            continue;
+        }
+
+        line_start = bytes[i] == b'\n';
+        i += 1;
+    }
+    parts.push((&text[start..], Segment::Text));
+
+    parts.retain(|(part, _)| !part.is_empty());
+    parts
+}
+
+fn scan_text<'a>(log_text: &str, attachments: &'a [Attachment], pieces: &mut Vec<Piece<'a>>) {
+    let mut text = String::new();
+    let mut chars = log_text.chars().peekable();
+    let mut push = |text: &mut String, piece: Piece<'a>| {
+        if !text.is_empty() {
+            pieces.push(Piece::Text(std::mem::take(text)));
+        }
+        pieces.push(piece);
+    };
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some('%') = chars.peek() {
+                text.push('%');
+                chars.next();
+            } else {
+                text.push(c);
+            }
+        } else if c == '[' && chars.clone().take(7).eq("[entry:".chars()) {
+            // [[entry:<id>]] or [[entry:<id>|<label>]]
+            let mut body = String::new();
+            let mut closed = false;
+            let mut ahead = chars.clone().skip(7);
+            while let Some(next) = ahead.next() {
+                if next == ']' && ahead.clone().next() == Some(']') {
+                    closed = true;
+                    break;
+                }
+                if next == '\n' {
+                    break;
+                }
+                body.push(next);
+            }
+
+            if closed {
+                // Consume "[entry:", the body and "]]"
+                for _ in 0..7 + body.chars().count() + 2 {
+                    chars.next();
+                }
+                push(&mut text, Piece::EntryLink(body));
+            } else {
+                text.push(c);
+            }
+        } else if c == '%' && chars.peek() == Some(&'{') {
+            // %{<target> <options>...}
+            chars.next();
+            // Within the line, as an unclosed one would take the text up to a later `}`
+            let mut body = String::new();
+            let mut closed = false;
+            while let Some(next) = chars.next_if(|next| *next != '\n') {
+                if next == '}' {
+                    closed = true;
+                    break;
+                }
+                body.push(next);
+            }
+
+            if !closed {
+                text.push_str("%{");
+                text.push_str(&body);
+            } else {
+                let reference = AttachmentRef::parse(&body)
+                    .and_then(|r| Ok((r.resolve(attachments)?, r.options)));
+                push(
+                    &mut text,
+                    Piece::Attachment(format!("%{{{}}}", body), reference),
+                );
+            }
+        } else if c == '%' {
+            let mut id_str = String::new();
+            while let Some(&next) = chars.peek() {
+                if next.is_numeric() {
+                    id_str.push(next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+
+            if let Ok(id) = id_str.parse::<u32>() {
+                let reference = attachments
+                    .iter()
+                    .find(|att| att.id == id)
+                    .map(|attachment| (attachment, RefOptions::default()))
+                    .ok_or_else(|| format!("no attachment %{}", id));
+                push(&mut text, Piece::Attachment(format!("%{}", id), reference));
+            } else {
+                text.push(c);
+                text.push_str(&id_str);
+            }
+        } else {
+            text.push(c);
+        }
+    }
+
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+}
+
+// Inside of a fenced block whose info string is `plot`
+// Other languages starting alike, e.g. ```plotly, are left as code
+fn plot_block_body(block: &str) -> Option<&str> {
+    let (first, body) = block.split_once('\n').unwrap_or((block, ""));
+    let info = first
+        .trim()
+        .strip_prefix("```")
+        .and_then(|info| info.strip_prefix("plot"))?;
+    if !(info.is_empty() || info.starts_with(char::is_whitespace)) {
+        return None;
+    }
+    Some(find_fence(body, "```").map_or(body, |end| &body[..end]))
+}
+
+// Position of a fence line starting with `marker`
+pub fn find_fence(text: &str, marker: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim_start().starts_with(marker) {
+            return Some(offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(id: u32, name: &str) -> Attachment {
+        Attachment {
+            id,
+            mime: "text/csv".to_string(),
+            original_name: name.to_string(),
+            download_token: format!("token-{}", id),
+            size: None,
+        }
+    }
+
+    // The pieces in short, e.g. "text:a", "ref:%1=1", "plot:2"
+    fn describe(pieces: &[Piece]) -> Vec<String> {
+        pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => format!("text:{}", text),
+                Piece::EntryLink(body) => format!("entry:{}", body),
+                Piece::Attachment(source, Ok((attachment, _))) => {
+                    format!("ref:{}={}", source, attachment.id)
+                }
+                Piece::Attachment(source, Err(_)) => format!("ref:{}=?", source),
+                Piece::Plot(Ok((attachment, _))) => format!("plot:{}", attachment.id),
+                Piece::Plot(Err(_)) => "plot:?".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn references_in_text() {
+        let attachments = [attachment(1, "a.csv"), attachment(2, "b c.csv")];
+        let pieces = split_references(
+            "see %1, %{\"b c.csv\" width=50%} and [[entry:42|the fault]] \\%1",
+            &attachments,
+        );
+        assert_eq!(
+            describe(&pieces),
+            [
+                "text:see ",
+                "ref:%1=1",
+                "text:, ",
+                "ref:%{\"b c.csv\" width=50%}=2",
+                "text: and ",
+                "entry:42|the fault",
+                "text: %1",
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_reference_stays_in_its_line() {
+        let attachments = [attachment(3, "a.csv")];
+        let pieces = split_references("%{3 width=50%\nnext line}", &attachments);
+        assert_eq!(describe(&pieces), ["text:%{3 width=50%\nnext line}"]);
+    }
+
+    #[test]
+    fn code_is_left_as_written() {
+        let attachments = [attachment(5, "a.csv")];
+        let pieces = split_references("`printf(\"%5d\")` %5\n```\n%5\n```\n", &attachments);
+        assert_eq!(
+            describe(&pieces),
+            [
+                "text:`printf(\"%5d\")`",
+                "text: ",
+                "ref:%5=5",
+                "text:\n",
+                "text:```\n%5\n```\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn plot_blocks() {
+        let attachments = [attachment(2, "a.csv")];
+        let pieces = split_references("before\n```plot\n%2\n```\nafter", &attachments);
+        assert_eq!(describe(&pieces), ["text:before\n", "plot:2", "text:after"]);
+
+        // Another language, or an example inside another block
+        let pieces = split_references("```plotly\n%2\n```\n", &attachments);
+        assert_eq!(describe(&pieces), ["text:```plotly\n%2\n```\n"]);
+        let pieces = split_references("~~~\n```plot\n%2\n```\n~~~\n", &attachments);
+        assert_eq!(describe(&pieces), ["text:~~~\n```plot\n%2\n```\n~~~\n"]);
+    }
+
+    #[test]
+    fn split_code_spans_and_blocks() {
+        assert_eq!(
+            split_code("a ``b ` c`` d\n~~~\nx\n~~~\ne `f"),
+            [
+                ("a ", Segment::Text),
+                ("``b ` c``", Segment::Span),
+                (" d\n", Segment::Text),
+                ("~~~\nx\n~~~\n", Segment::Block),
+                ("e `f", Segment::Text),
+            ]
+        );
+        // Unclosed block runs to the end
+        assert_eq!(
+            split_code("日本語\n```\nコード"),
+            [("日本語\n", Segment::Text), ("```\nコード", Segment::Block)]
+        );
+    }
+
+    #[test]
+    fn parse_reference_options() {
+        let reference = AttachmentRef::parse("3 width=50% page=2 thumb").unwrap();
+        assert_eq!(reference.target, RefTarget::Id(3));
+        assert_eq!(reference.options.width.as_deref(), Some("50%"));
+        assert_eq!(reference.options.page, Some(2));
+        assert!(reference.options.thumbnail);
+        assert!(AttachmentRef::parse("3 page=0").is_err());
+        assert!(AttachmentRef::parse("\"unterminated").is_err());
+    }
+}
@@ -1,103 +1,413 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
 use pulldown_cmark::{html, Parser};
 use yew::prelude::*;
 use yew::virtual_dom::VNode;
 
+use crate::i18n::*;
 use crate::models::*;
+use crate::plot::*;
+use crate::preview::*;
+use crate::references::*;
+use crate::Msg;
+
+// Markdown of an entry converted to html, with the attachments referenced from the text
+#[derive(Clone)]
+pub struct RenderedEntry {
+    pub revision: u64,
+    pub html: AttrValue,
+    pub referenced: Rc<HashSet<u32>>,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct EntryBodyProps {
+    pub html: AttrValue,
+}
+
+// The rendered markdown of an entry
+// Its DOM node is kept as long as the html does not change, together with what
+// preview.js has loaded into it
+pub struct EntryBody {
+    node: web_sys::Node,
+}
+
+impl Component for EntryBody {
+    type Message = ();
+    type Properties = EntryBodyProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            node: html_node(&ctx.props().html),
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props().html == old_props.html {
+            return false;
+        }
+        self.node = html_node(&ctx.props().html);
+        true
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        VNode::VRef(self.node.clone())
+    }
+}
 
 impl Model {
     // Convert markdown to html
-    pub fn markdown_to_html(&self, entry: &Entry) -> Html {
-        let log_with_attachments = self.parse_log_text(&entry.log, &entry.attachments);
+    // Also returns the ids of the attachments referenced from the text
+    pub fn markdown_to_html(&self, entry: &Entry) -> (Html, Rc<HashSet<u32>>) {
+        let rendered = self.rendered_entry(entry);
+        (
+            html! { <EntryBody html={rendered.html} /> },
+            rendered.referenced,
+        )
+    }
+
+    // The conversion is done once per entry revision
+    pub fn rendered_entry(&self, entry: &Entry) -> RenderedEntry {
+        let cached = self
+            .rendered
+            .borrow()
+            .get(&entry.id)
+            .filter(|rendered| rendered.revision == entry.revision)
+            .cloned();
+        cached.unwrap_or_else(|| {
+            let rendered = self.render_entry(entry);
+            self.rendered
+                .borrow_mut()
+                .insert(entry.id.clone(), rendered.clone());
+            rendered
+        })
+    }
+
+    fn render_entry(&self, entry: &Entry) -> RenderedEntry {
+        let mut referenced = HashSet::new();
+        let log_with_attachments =
+            self.parse_log_text(&entry.log, &entry.attachments, &mut referenced);
         let parser = Parser::new(&log_with_attachments);
         let mut html_output = String::new();
         html::push_html(&mut html_output, parser);
 
-        // HTML文字列をDOMノードに変換し、YewのVNodeとして返す
-        let document = web_sys::window().unwrap().document().unwrap();
-        let div = document.create_element("div").unwrap();
-        div.set_inner_html(&html_output);
-        VNode::VRef(div.into())
+        RenderedEntry {
+            revision: entry.revision,
+            html: html_output.into(),
+            referenced: Rc::new(referenced),
+        }
     }
 
-    fn parse_log_text(&self, log_text: &str, attachments: &[Attachment]) -> String {
-        let mut result = String::new();
-        let mut chars = log_text.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            if c == '\\' {
-                if let Some('%') = chars.peek() {
-                    result.push('%');
-                    chars.next();
-                } else {
-                    result.push(c);
-                }
-            } else if c == '%' {
-                let mut id_str = String::new();
-                while let Some(&next) = chars.peek() {
-                    if next.is_numeric() {
-                        id_str.push(next);
-                        chars.next();
-                    } else {
-                        break;
+    // The bar listing all the attachments of an entry
+    pub fn attachment_strip(
+        &self,
+        ctx: &Context<Self>,
+        entry: &Entry,
+        referenced: &HashSet<u32>,
+    ) -> Html {
+        if entry.attachments.is_empty() {
+            return html! {};
+        }
+
+        html! {
+            <div class="attachment-strip">
+                <div class="attachment-chips">
+                    {
+                        for entry.attachments.iter().map(|attachment| {
+                            let token = attachment.download_token.clone();
+                            let onclick = ctx
+                                .link()
+                                .callback(move |_| Msg::ToggleAttachmentPreview(token.clone()));
+                            let mut classes = classes!("attachment-chip");
+                            if referenced.contains(&attachment.id) {
+                                classes.push("referenced");
+                            }
+                            if self.opened_attachments.contains(&attachment.download_token) {
+                                classes.push("opened");
+                            }
+                            let meta = match attachment.size {
+                                Some(size) => format!("{} · {}", format_size(size), attachment.mime),
+                                None => attachment.mime.clone(),
+                            };
+                            html! {
+                                <button
+                                    class={classes}
+                                    title={attachment.original_name.clone()}
+                                    aria-expanded={self.opened_attachments.contains(&attachment.download_token).to_string()}
+                                    {onclick}
+                                >
+                                    <i class="material-symbols-outlined attachment-icon" aria-hidden="true">{ mime_icon(&attachment.mime) }</i>
+                                    <span class="attachment-number">{ format!("%{}", attachment.id) }</span>
+                                    <span class="attachment-name">{ &attachment.original_name }</span>
+                                    <span class="attachment-meta">{ meta }</span>
+                                </button>
+                            }
+                        })
                     }
+                </div>
+                {
+                    for entry.attachments.iter()
+                        .filter(|attachment| self.opened_attachments.contains(&attachment.download_token))
+                        // Kept over the re-renders, with what preview.js has loaded into them
+                        .map(|attachment| html! {
+                            <div class="attachment-strip-preview" key={attachment.download_token.clone()}>
+                                <EntryBody html={AttrValue::from(self.expand_attachment_html(attachment, &RefOptions::default()))} />
+                            </div>
+                        })
                 }
+            </div>
+        }
+    }
 
-                if let Ok(id) = id_str.parse::<u32>() {
-                    if let Some(attachment) = attachments.iter().find(|att| att.id == id) {
-                        result.push_str(&self.expand_attachment_html(attachment));
-                    } else {
-                        result.push_str(&format!("%{}", id));
-                    }
-                } else {
-                    result.push(c);
+    fn parse_log_text(
+        &self,
+        log_text: &str,
+        attachments: &[Attachment],
+        referenced: &mut HashSet<u32>,
+    ) -> String {
+        let mut result = String::new();
+        for piece in split_references(log_text, attachments) {
+            match piece {
+                Piece::Text(text) => result.push_str(&text),
+                Piece::EntryLink(body) => result.push_str(&entry_link_html(&body)),
+                Piece::Attachment(_, Ok((attachment, options))) => {
+                    referenced.insert(attachment.id);
+                    result.push_str(&self.expand_attachment_html(attachment, &options));
+                }
+                Piece::Attachment(source, Err(err)) => {
+                    result.push_str(&reference_error_html(&source, &err))
+                }
+                // Blank lines around to keep it an HTML block for the markdown
+                Piece::Plot(Ok((attachment, options))) => {
+                    referenced.insert(attachment.id);
+                    result.push_str(&format!("\n{}\n\n", self.plot_html(attachment, &options)));
+                }
+                Piece::Plot(Err(err)) => {
+                    result.push_str(&format!("\n{}\n\n", reference_error_html("```plot", &err)))
                 }
-            } else {
-                result.push(c);
             }
         }
 
         result
     }
 
-    fn expand_attachment_html(&self, attachment: &Attachment) -> String {
-        use html_escape::encode_text;
+    fn plot_html(&self, attachment: &Attachment, options: &PlotOptions) -> String {
+        use html_escape::encode_single_quoted_attribute as attr;
+
+        let delimiter = match PreviewKind::of(attachment) {
+            PreviewKind::Table(delimiter) => delimiter,
+            _ => "comma",
+        };
+        let options = serde_json::to_string(options).unwrap_or_default();
+        // Every value escaped for the single quotes, as the file names can hold them
+        format!(
+            "<div class='plot-attachment' data-url='{}' data-id='{}' data-delimiter='{}' data-options='{}' name='{}' role='figure' aria-label='{}' aria-busy='true'>{}</div>",
+            attr(&self.download_url(attachment)),
+            attr(&attachment.download_token),
+            delimiter,
+            attr(&options),
+            attr(&attachment.original_name),
+            attr(&attachment.original_name),
+            self.t(Text::LoadingChart)
+        )
+    }
 
-        let path = format!(
+    pub fn download_url(&self, attachment: &Attachment) -> String {
+        format!(
             "http://127.0.0.1:8080/download?client={}&token={}",
             self.client_hash, attachment.download_token
-        );
+        )
+    }
+
+    pub fn expand_attachment_html(&self, attachment: &Attachment, options: &RefOptions) -> String {
+        use html_escape::encode_single_quoted_attribute as attr;
+
+        // All in single-quoted attributes, so escaped for them, quotes included
+        let path = attr(&self.download_url(attachment)).into_owned();
 
-        match attachment.mime.as_str() {
-            "image/png" | "image/jpeg" | "image/gif" => {
+        // Common options as attributes of the preview element
+        let mut attrs = String::new();
+        if let Some(width) = &options.width {
+            attrs.push_str(&format!(" style='width:{}'", width));
+        }
+        if let Some(page) = options.page {
+            attrs.push_str(&format!(" data-page='{}'", page));
+        }
+        let thumbnail = if options.thumbnail { " thumbnail" } else { "" };
+
+        let name = attr(&attachment.original_name);
+        let token = attr(&attachment.download_token);
+        // Named for the screen readers, and busy until preview.js has loaded it
+        let loading = format!(
+            "{} role='figure' aria-label='{}' aria-busy='true'",
+            attrs, name
+        );
+        match PreviewKind::of(attachment) {
+            _ if options.link_only => download_link_html(&path, attachment, self.t(Text::Download)),
+            PreviewKind::Table(_) if options.plot => {
+                self.plot_html(attachment, &PlotOptions::default())
+            }
+            PreviewKind::Image => {
                 format!(
-                "<div class='image-attachment' data-url='{}' data-id='{}' name='{}'>Loading image preview...</div>",
-                path,
-                attachment.download_token,
-                encode_text(&attachment.original_name)
+                "<div class='image-attachment{}' data-url='{}' data-id='{}' name='{}'{}>{}</div>",
+                thumbnail, path, token, name, loading, self.t(Text::LoadingImage)
             )
             }
-            "application/pdf" => {
+            PreviewKind::Svg => {
                 format!(
-                "<div class='pdf-attachment' data-url='{}' data-id='{}'>Loading PDF preview...</div>",
-                path,
-                attachment.download_token
+                    "<div class='svg-attachment{}' data-url='{}' data-id='{}' name='{}'{}>{}</div>",
+                    thumbnail,
+                    path,
+                    token,
+                    name,
+                    loading,
+                    self.t(Text::LoadingImage)
+                )
+            }
+            PreviewKind::Audio => {
+                format!(
+                "<div class='audio-attachment{}' data-id='{}' name='{}'{}><audio controls preload='metadata' src='{}'></audio></div>",
+                thumbnail, token, name, attrs, path
             )
             }
-            "text/plain" => {
+            PreviewKind::Video => {
                 format!(
-                "<div class='text-attachment' data-url='{}' data-id='{}'>Loading preview...</div>",
-                path, attachment.download_token
+                "<div class='video-attachment{}' data-id='{}' name='{}'{}><video controls preload='metadata' src='{}'></video></div>",
+                thumbnail, token, name, attrs, path
             )
             }
-            _ => {
+            PreviewKind::Pdf => {
                 format!(
-                "<a href='{}' download='{}' class='attachment-download' data-id='{}'>Download {}</a>",
-                path,
-                encode_text(&attachment.original_name),
-                attachment.download_token,
-                encode_text(&attachment.original_name)
+                    "<div class='pdf-attachment{}' data-url='{}' data-id='{}'{}>{}</div>",
+                    thumbnail,
+                    path,
+                    token,
+                    loading,
+                    self.t(Text::LoadingPdf)
+                )
+            }
+            PreviewKind::Text => {
+                format!(
+                    "<div class='text-attachment{}' data-url='{}' data-id='{}'{}>{}</div>",
+                    thumbnail,
+                    path,
+                    token,
+                    loading,
+                    self.t(Text::LoadingPreview)
+                )
+            }
+            PreviewKind::Table(delimiter) => {
+                format!(
+                "<div class='table-attachment{}' data-url='{}' data-id='{}' data-delimiter='{}' name='{}'{}>{}</div>",
+                thumbnail, path, token, delimiter, name, loading, self.t(Text::LoadingTable)
             )
             }
+            PreviewKind::Tree(format) => {
+                format!(
+                "<div class='tree-attachment{}' data-url='{}' data-id='{}' data-format='{}' name='{}'{}>{}</div>",
+                thumbnail, path, token, format, name, loading, self.t(Text::LoadingPreview)
+            )
+            }
+            PreviewKind::Code(language) => {
+                format!(
+                "<div class='code-attachment{}' data-url='{}' data-id='{}' data-language='{}' name='{}'{}>{}</div>",
+                thumbnail, path, token, language, name, loading, self.t(Text::LoadingPreview)
+            )
+            }
+            PreviewKind::None => download_link_html(&path, attachment, self.t(Text::Download)),
         }
     }
 }
+
+// `path` is escaped for the attribute already
+fn download_link_html(path: &str, attachment: &Attachment, label: &str) -> String {
+    use html_escape::{encode_single_quoted_attribute, encode_text};
+
+    format!(
+        "<a href='{}' download='{}' class='attachment-download' data-id='{}'>{} {}</a>",
+        path,
+        encode_single_quoted_attribute(&attachment.original_name),
+        encode_single_quoted_attribute(&attachment.download_token),
+        label,
+        encode_text(&attachment.original_name)
+    )
+}
+
+// Link to another entry, routed by the URL hash
+fn entry_link_html(body: &str) -> String {
+    use html_escape::encode_text;
+
+    let (id, label) = match body.split_once('|') {
+        Some((id, label)) => (id.trim(), Some(label.trim())),
+        None => (body.trim(), None),
+    };
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return reference_error_html(
+            &format!("[[entry:{}]]", body),
+            &format!("invalid entry id `{}`", id),
+        );
+    }
+
+    format!(
+        "<a class='entry-link' href='#entry-{}' data-entry='{}'>{}</a>",
+        id,
+        id,
+        encode_text(label.unwrap_or(&format!("entry {}", id)))
+    )
+}
+
+// Visible marker for a reference which cannot be expanded
+fn reference_error_html(source: &str, reason: &str) -> String {
+    use html_escape::{encode_double_quoted_attribute, encode_text};
+
+    format!(
+        "<span class=\"attachment-error\" title=\"{}\">{}</span>",
+        encode_double_quoted_attribute(reason),
+        encode_text(source)
+    )
+}
+
+// HTML文字列をDOMノードに変換する
+fn html_node(html: &str) -> web_sys::Node {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let div = document.create_element("div").unwrap();
+    div.set_inner_html(html);
+    div.into()
+}
+
+// Icons (see. https://fonts.google.com/icons), same choices as the editor previews
+fn mime_icon(mime: &str) -> &'static str {
+    match mime {
+        _ if mime.starts_with("image/") => "image",
+        _ if mime.starts_with("audio/") => "audio_file",
+        _ if mime.starts_with("video/") => "video_file",
+        "application/pdf" => "picture_as_pdf",
+        "text/plain" => "description",
+        "text/html" => "html",
+        "text/javascript" => "javascript",
+        _ if mime.contains("css") => "css",
+        _ if mime.starts_with("text/") => "code",
+        _ if mime.contains("json") => "html",
+        _ if mime.contains("zip") => "folder_zip",
+        _ => "insert_drive_file",
+    }
+}
+
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "kB", "MB", "GB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", size, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
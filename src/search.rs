@@ -0,0 +1,53 @@
+use web_sys::{HtmlInputElement, KeyboardEvent};
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::Msg;
+
+impl Model {
+    // Whether the entry contains all the words searched for
+    pub fn matches_search(&self, entry: &Entry) -> bool {
+        let search = self.search.trim().to_lowercase();
+        if search.is_empty() {
+            return true;
+        }
+        let log = entry.log.to_lowercase();
+        let author = entry.author.as_deref().unwrap_or("").to_lowercase();
+        search.split_whitespace().all(|word| {
+            log.contains(word)
+                || author.contains(word)
+                || entry
+                    .tags
+                    .iter()
+                    .any(|tag| tag.contains(word.trim_start_matches('#')))
+        })
+    }
+
+    // Sidebar section searching the loaded entries
+    pub fn search_box(&self, ctx: &Context<Self>) -> Html {
+        let oninput = ctx.link().callback(|e: InputEvent| {
+            Msg::UpdateSearch(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        // Back to the timeline, for the keyboard navigation
+        let onkeydown = Callback::from(|e: KeyboardEvent| {
+            if e.key() == "Escape" || e.key() == "Enter" {
+                let _ = e.target_unchecked_into::<web_sys::HtmlElement>().blur();
+            }
+        });
+        html! {
+            <section class="search-sidebar" role="search">
+                <input
+                    ref={self.search_ref.clone()}
+                    class="search-input"
+                    type="search"
+                    aria-label={self.t(Text::Search)}
+                    placeholder={self.t(Text::SearchPlaceholder)}
+                    value={self.search.clone()}
+                    {oninput}
+                    {onkeydown}
+                />
+            </section>
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::{BTreeSet, HashSet};
+
+use serde::Deserialize;
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::timezone::*;
+use crate::Msg;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Problem,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [
+        Severity::Info,
+        Severity::Warning,
+        Severity::Problem,
+        Severity::Critical,
+    ];
+
+    // As sent to the backend and used in the class names
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Problem => "problem",
+            Severity::Critical => "critical",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "Info",
+            Severity::Warning => "Warning",
+            Severity::Problem => "Problem",
+            Severity::Critical => "Critical",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|severity| severity.as_str() == value)
+            .unwrap_or_default()
+    }
+}
+
+// Severities shown in the timeline
+#[derive(Debug)]
+pub struct SeverityFilter {
+    pub visible: BTreeSet<Severity>,
+}
+
+impl Default for SeverityFilter {
+    fn default() -> Self {
+        Self {
+            visible: Severity::ALL.into_iter().collect(),
+        }
+    }
+}
+
+impl SeverityFilter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.visible.contains(&entry.severity)
+    }
+
+    pub fn toggle(&mut self, severity: Severity) {
+        if !self.visible.remove(&severity) {
+            self.visible.insert(severity);
+        }
+    }
+}
+
+// Acknowledged critical entries are remembered over reloads
+const ACKNOWLEDGED_KEY: &str = "elogbook.acknowledged";
+
+pub fn load_acknowledged() -> HashSet<String> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(ACKNOWLEDGED_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_acknowledged(acknowledged: &HashSet<String>) {
+    if let Some(storage) =
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    {
+        let json = serde_json::to_string(acknowledged).unwrap_or_default();
+        let _ = storage.set_item(ACKNOWLEDGED_KEY, &json);
+    }
+}
+
+impl Model {
+    pub fn severity_badge(&self, entry: &Entry) -> Html {
+        if entry.severity == Severity::Info {
+            return html! {};
+        }
+        html! {
+            <span class={classes!("severity-badge", entry.severity.as_str())}>
+                { self.t(Text::SeverityName(entry.severity)) }
+            </span>
+        }
+    }
+
+    // Sidebar section toggling the severities shown
+    pub fn severity_sidebar(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <section class="severity-sidebar">
+                <div class="sidebar-header"><span>{ self.t(Text::Severity) }</span></div>
+                <ul class="severity-list">
+                    {
+                        for Severity::ALL.into_iter().map(|severity| {
+                            let visible = self.severity_filter.visible.contains(&severity);
+                            let count = self.entries.iter().filter(|entry| entry.severity == severity).count();
+                            let onclick = ctx.link().callback(move |_| Msg::ToggleSeverityFilter(severity));
+                            html! {
+                                <li>
+                                    <button class={classes!("severity-item", severity.as_str(), (!visible).then_some("hidden"))} aria-pressed={visible.to_string()} {onclick}>
+                                        <span class="severity-name">{ self.t(Text::SeverityName(severity)) }</span>
+                                        <span class="tag-count">{ count }</span>
+                                    </button>
+                                </li>
+                            }
+                        })
+                    }
+                </ul>
+            </section>
+        }
+    }
+
+    // Banner in the header listing the critical entries not acknowledged yet
+    pub fn critical_banner(&self, ctx: &Context<Self>) -> Html {
+        let critical: Vec<&Entry> = self
+            .entries
+            .iter()
+            .filter(|entry| {
+                entry.severity == Severity::Critical && !self.acknowledged.contains(&entry.id)
+            })
+            .collect();
+        if critical.is_empty() {
+            return html! {};
+        }
+
+        html! {
+            <div class="critical-banner">
+                <ul>
+                    {
+                        for critical.iter().rev().map(|entry| {
+                            let show = {
+                                let id = entry.id.clone();
+                                ctx.link().callback(move |_| Msg::ShowEntry(id.clone()))
+                            };
+                            let acknowledge = {
+                                let id = entry.id.clone();
+                                ctx.link().callback(move |_| Msg::AcknowledgeCritical(Some(id.clone())))
+                            };
+                            html! {
+                                <li>
+                                    <span class="severity-badge critical">{ self.t(Text::SeverityName(Severity::Critical)) }</span>
+                                    <span class="critical-time" title={utc_title(&entry.timestamp)}>
+                                        { self.zone.format(&entry.timestamp, "%Y-%m-%d %H:%M") }
+                                    </span>
+                                    <button class="critical-summary" onclick={show}>{ entry.summary(80) }</button>
+                                    <button onclick={acknowledge}>{ self.t(Text::Acknowledge) }</button>
+                                </li>
+                            }
+                        })
+                    }
+                </ul>
+                if critical.len() > 1 {
+                    <button onclick={ctx.link().callback(|_| Msg::AcknowledgeCritical(None))}>
+                        { self.t(Text::AcknowledgeAll) }
+                    </button>
+                }
+            </div>
+        }
+    }
+}
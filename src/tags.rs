@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::Msg;
+
+// Collect `#hashtags` from the markdown, skipping code blocks and code spans
+// A tag must follow a whitespace or the line head so that headings (`# Title`)
+// and URL fragments (`page#anchor`) are not taken.
+pub fn parse_hashtags(text: &str) -> Vec<String> {
+    let mut tags = vec![];
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut prev = ' ';
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '#' && !in_code && prev.is_whitespace() {
+                let mut tag = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || matches!(next, '_' | '-' | '/') {
+                        tag.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                // Pure numbers like "#1" are not tags
+                if tag.chars().any(|c| !c.is_numeric()) {
+                    tags.push(normalize_tag(&tag));
+                }
+                prev = tag.chars().last().unwrap_or(c);
+                continue;
+            }
+            prev = c;
+        }
+    }
+
+    tags
+}
+
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+// Explicit tags typed as "rf, beam loss" or "#rf #beam-loss"
+pub fn parse_tag_list(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .map(normalize_tag)
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+// Merge the tags keeping the first appearance order
+pub fn merge_tags(explicit: Vec<String>, parsed: Vec<String>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    explicit
+        .into_iter()
+        .chain(parsed)
+        .filter(|tag| seen.insert(tag.clone()))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TagMatch {
+    // Entries having any of the selected tags
+    #[default]
+    Any,
+    // Entries having all the selected tags
+    All,
+}
+
+#[derive(Debug, Default)]
+pub struct TagFilter {
+    pub tags: BTreeSet<String>,
+    pub mode: TagMatch,
+}
+
+impl TagFilter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        if self.tags.is_empty() {
+            return true;
+        }
+        match self.mode {
+            TagMatch::Any => self.tags.iter().any(|tag| entry.tags.contains(tag)),
+            TagMatch::All => self.tags.iter().all(|tag| entry.tags.contains(tag)),
+        }
+    }
+
+    pub fn toggle(&mut self, tag: String) {
+        if !self.tags.remove(&tag) {
+            self.tags.insert(tag);
+        }
+    }
+}
+
+impl Model {
+    // Tag chips shown on an entry
+    pub fn tag_chips(&self, ctx: &Context<Self>, entry: &Entry) -> Html {
+        if entry.tags.is_empty() {
+            return html! {};
+        }
+
+        html! {
+            <div class="tag-chips">
+                {
+                    for entry.tags.iter().map(|tag| {
+                        let selected = self.tag_filter.tags.contains(tag);
+                        let value = tag.clone();
+                        let onclick = ctx.link().callback(move |_| Msg::ToggleTagFilter(value.clone()));
+                        html! {
+                            <button class={classes!("tag-chip", selected.then_some("selected"))} {onclick}>
+                                { format!("#{}", tag) }
+                            </button>
+                        }
+                    })
+                }
+            </div>
+        }
+    }
+
+    // Sidebar section listing the tags of the loaded entries with the filter controls
+    pub fn tag_sidebar(&self, ctx: &Context<Self>) -> Html {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for tag in self.entries.iter().flat_map(|entry| entry.tags.iter()) {
+            *counts.entry(tag.as_str()).or_default() += 1;
+        }
+        // Selected tags stay listed even without any loaded entry
+        for tag in self.tag_filter.tags.iter() {
+            counts.entry(tag.as_str()).or_default();
+        }
+
+        let mode = self.tag_filter.mode;
+        let mode_button = |value: TagMatch, label: &str| {
+            let onclick = ctx.link().callback(move |_| Msg::SetTagMatch(value));
+            html! {
+                <button class={classes!("tag-mode", (mode == value).then_some("selected"))} {onclick}>
+                    { label }
+                </button>
+            }
+        };
+
+        html! {
+            <section class="tag-sidebar">
+                <div class="sidebar-header">
+                    <span>{ self.t(Text::Tags) }</span>
+                    { mode_button(TagMatch::Any, self.t(Text::MatchAny)) }
+                    { mode_button(TagMatch::All, self.t(Text::MatchAll)) }
+                    if !self.tag_filter.tags.is_empty() {
+                        <button class="tag-clear" onclick={ctx.link().callback(|_| Msg::ClearTagFilter)}>
+                            { self.t(Text::Clear) }
+                        </button>
+                    }
+                </div>
+                <ul class="tag-list">
+                    {
+                        for counts.into_iter().map(|(tag, count)| {
+                            let selected = self.tag_filter.tags.contains(tag);
+                            let value = tag.to_string();
+                            let onclick = ctx.link().callback(move |_| Msg::ToggleTagFilter(value.clone()));
+                            html! {
+                                <li>
+                                    <button class={classes!("tag-item", selected.then_some("selected"))} aria-pressed={selected.to_string()} {onclick}>
+                                        <span class="tag-name">{ format!("#{}", tag) }</span>
+                                        <span class="tag-count">{ count }</span>
+                                    </button>
+                                </li>
+                            }
+                        })
+                    }
+                </ul>
+            </section>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashtags_in_text() {
+        assert_eq!(
+            parse_hashtags("#RF trip at the #beam-loss monitor\n# Heading\nsee page#anchor"),
+            ["rf", "beam-loss"]
+        );
+        // Numbers, code spans and blocks are not tags
+        assert_eq!(
+            parse_hashtags("issue #12 `#code`\n```\n#block\n```\n#after #日本語"),
+            ["after", "日本語"]
+        );
+    }
+
+    #[test]
+    fn tag_lists() {
+        assert_eq!(parse_tag_list("rf, Beam-Loss"), ["rf", "beam-loss"]);
+        assert_eq!(parse_tag_list("#rf  #vacuum,,"), ["rf", "vacuum"]);
+        assert!(parse_tag_list(" , ").is_empty());
+    }
+
+    #[test]
+    fn merged_in_order_without_duplicates() {
+        let explicit = vec!["rf".to_string(), "vacuum".to_string()];
+        let parsed = vec!["beam".to_string(), "rf".to_string()];
+        assert_eq!(merge_tags(explicit, parsed), ["rf", "vacuum", "beam"]);
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::severity::*;
+use crate::Msg;
+
+// A fixed format of entries, e.g. shift handovers (see. public/templates.json)
+#[derive(Debug, Clone, Deserialize)]
+pub struct Template {
+    pub id: String,
+    pub name: String,
+    pub fields: Vec<TemplateField>,
+    // Preset of the entry metadata
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub severity: Option<Severity>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateField {
+    pub name: String,
+    pub label: String,
+    #[serde(flatten)]
+    pub kind: FieldKind,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FieldKind {
+    Text {
+        #[serde(default)]
+        multiline: bool,
+    },
+    Number {
+        unit: Option<String>,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Select {
+        options: Vec<String>,
+    },
+    Checkbox,
+    Date,
+}
+
+// Machine-readable values of a templated entry, stored with the entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryFields {
+    pub template: String,
+    pub values: BTreeMap<String, serde_json::Value>,
+}
+
+impl TemplateField {
+    // Check the raw input and convert it into the stored value
+    fn value(&self, raw: &str, locale: Locale) -> Result<Option<serde_json::Value>, String> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return match (&self.kind, self.required) {
+                (FieldKind::Checkbox, _) => Ok(Some(false.into())),
+                (_, true) => Err(locale.text(Text::Required).to_string()),
+                (_, false) => Ok(None),
+            };
+        }
+
+        match &self.kind {
+            FieldKind::Text { .. } => Ok(Some(raw.into())),
+            FieldKind::Number { min, max, .. } => {
+                let value = raw
+                    .parse::<f64>()
+                    .map_err(|_| locale.text(Text::NotANumber).to_string())?;
+                if let Some(min) = min {
+                    if value < *min {
+                        return Err(format!("{} {}", locale.text(Text::AtLeast), min));
+                    }
+                }
+                if let Some(max) = max {
+                    if value > *max {
+                        return Err(format!("{} {}", locale.text(Text::AtMost), max));
+                    }
+                }
+                // Integers as typed, not as "12.0"
+                match raw.parse::<i64>() {
+                    Ok(integer) => Ok(Some(integer.into())),
+                    Err(_) => {
+                        Ok(serde_json::Number::from_f64(value).map(serde_json::Value::Number))
+                    }
+                }
+            }
+            FieldKind::Select { options } => {
+                if options.iter().any(|option| option == raw) {
+                    Ok(Some(raw.into()))
+                } else {
+                    Err(locale.text(Text::NotAChoice).to_string())
+                }
+            }
+            FieldKind::Checkbox => Ok(Some((raw == "true").into())),
+            FieldKind::Date => NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .map(|_| Some(raw.into()))
+                .map_err(|_| format!("{} (YYYY-MM-DD)", locale.text(Text::NotADate))),
+        }
+    }
+
+    // How the value reads in the markdown
+    fn markdown(&self, value: &serde_json::Value) -> String {
+        match (&self.kind, value) {
+            (FieldKind::Checkbox, serde_json::Value::Bool(checked)) => {
+                if *checked { "yes" } else { "no" }.to_string()
+            }
+            (
+                FieldKind::Number {
+                    unit: Some(unit), ..
+                },
+                value,
+            ) => format!("{} {}", value, unit),
+            (_, serde_json::Value::String(text)) => text.replace('\n', " "),
+            (_, value) => value.to_string(),
+        }
+    }
+}
+
+impl Template {
+    // Validate the inputs, and produce the markdown and the field values
+    // On failure, the error messages are returned by field name
+    pub fn compile(
+        &self,
+        inputs: &BTreeMap<String, String>,
+        locale: Locale,
+    ) -> Result<(String, EntryFields), BTreeMap<String, String>> {
+        let mut errors = BTreeMap::new();
+        let mut values = BTreeMap::new();
+        let mut markdown = format!("### {}\n\n", self.name);
+
+        for field in self.fields.iter() {
+            let raw = inputs.get(&field.name).map(String::as_str).unwrap_or("");
+            match field.value(raw, locale) {
+                Ok(Some(value)) => {
+                    markdown.push_str(&format!(
+                        "- **{}:** {}\n",
+                        field.label,
+                        field.markdown(&value)
+                    ));
+                    values.insert(field.name.clone(), value);
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    errors.insert(field.name.clone(), err);
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            let fields = EntryFields {
+                template: self.id.clone(),
+                values,
+            };
+            Ok((markdown, fields))
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Model {
+    pub fn selected_template(&self) -> Option<&Template> {
+        let id = self.draft.template.as_ref()?;
+        self.templates.iter().find(|template| &template.id == id)
+    }
+
+    // Form of the selected template, shown above the editor
+    pub fn template_form(&self, ctx: &Context<Self>) -> Html {
+        let Some(template) = self.selected_template() else {
+            return html! {};
+        };
+
+        html! {
+            <div class="template-form">
+                <div class="template-form-header">
+                    <span>{ &template.name }</span>
+                    <button class="close-button" aria-label={self.t(Text::Close)} onclick={ctx.link().callback(|_| Msg::SelectTemplate(None))}>
+                        {"×"}
+                    </button>
+                </div>
+                <div class="template-fields">
+                    { for template.fields.iter().map(|field| self.template_field(ctx, field)) }
+                </div>
+            </div>
+        }
+    }
+
+    fn template_field(&self, ctx: &Context<Self>, field: &TemplateField) -> Html {
+        let value = self
+            .draft
+            .fields
+            .get(&field.name)
+            .cloned()
+            .unwrap_or_default();
+        let error = self.draft.errors.get(&field.name);
+        let name = field.name.clone();
+
+        let input = match &field.kind {
+            FieldKind::Text { multiline: true } => {
+                let oninput = ctx.link().callback(move |e: InputEvent| {
+                    let value = e.target_unchecked_into::<HtmlTextAreaElement>().value();
+                    Msg::UpdateTemplateField(name.clone(), value)
+                });
+                html! { <textarea rows="2" {value} {oninput}/> }
+            }
+            FieldKind::Select { options } => {
+                let onchange = ctx.link().callback(move |e: Event| {
+                    let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+                    Msg::UpdateTemplateField(name.clone(), value)
+                });
+                html! {
+                    <select {onchange}>
+                        <option value="" selected={value.is_empty()}>{"—"}</option>
+                        {
+                            for options.iter().map(|option| html! {
+                                <option value={option.clone()} selected={option == &value}>{ option }</option>
+                            })
+                        }
+                    </select>
+                }
+            }
+            FieldKind::Checkbox => {
+                let onchange = ctx.link().callback(move |e: Event| {
+                    let checked = e.target_unchecked_into::<HtmlInputElement>().checked();
+                    Msg::UpdateTemplateField(name.clone(), checked.to_string())
+                });
+                html! { <input type="checkbox" checked={value == "true"} {onchange}/> }
+            }
+            kind => {
+                let input_type = match kind {
+                    FieldKind::Number { .. } => "number",
+                    FieldKind::Date => "date",
+                    _ => "text",
+                };
+                let oninput = ctx.link().callback(move |e: InputEvent| {
+                    let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                    Msg::UpdateTemplateField(name.clone(), value)
+                });
+                html! { <input type={input_type} {value} {oninput}/> }
+            }
+        };
+        let unit = match &field.kind {
+            FieldKind::Number {
+                unit: Some(unit), ..
+            } => html! { <span class="field-unit">{ unit }</span> },
+            _ => html! {},
+        };
+
+        html! {
+            <label class={classes!("template-field", error.is_some().then_some("invalid"))}>
+                <span class="field-label">
+                    { &field.label }
+                    if field.required { <span class="field-required">{"*"}</span> }
+                </span>
+                <span class="field-input">{ input }{ unit }</span>
+                if let Some(error) = error {
+                    <span class="field-error">{ error }</span>
+                }
+            </label>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template() -> Template {
+        serde_json::from_str(
+            r#"{
+                "id": "handover",
+                "name": "Shift handover",
+                "fields": [
+                    { "name": "shift", "label": "Shift", "type": "select", "options": ["Owl", "Day"], "required": true },
+                    { "name": "date", "label": "Date", "type": "date" },
+                    { "name": "current", "label": "Beam current", "type": "number", "unit": "mA", "min": 0, "max": 500 },
+                    { "name": "notes", "label": "Notes", "type": "text", "multiline": true },
+                    { "name": "cleared", "label": "Alarms cleared", "type": "checkbox" }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn inputs(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn compiled_into_markdown_and_values() {
+        let (markdown, fields) = template()
+            .compile(
+                &inputs(&[
+                    ("shift", "Day"),
+                    ("date", "2024-05-01"),
+                    ("current", " 12 "),
+                    ("notes", "first\nsecond"),
+                ]),
+                Locale::English,
+            )
+            .unwrap();
+        assert_eq!(
+            markdown,
+            "### Shift handover\n\n\
+             - **Shift:** Day\n\
+             - **Date:** 2024-05-01\n\
+             - **Beam current:** 12 mA\n\
+             - **Notes:** first second\n\
+             - **Alarms cleared:** no\n"
+        );
+        assert_eq!(fields.template, "handover");
+        // Integers as typed, the unchecked box as false, nothing for the others
+        assert_eq!(fields.values["current"], serde_json::json!(12));
+        assert_eq!(fields.values["cleared"], serde_json::json!(false));
+        assert_eq!(fields.values.len(), 5);
+    }
+
+    #[test]
+    fn optional_fields_left_out() {
+        let (markdown, fields) = template()
+            .compile(&inputs(&[("shift", "Owl")]), Locale::English)
+            .unwrap();
+        assert_eq!(
+            markdown,
+            "### Shift handover\n\n- **Shift:** Owl\n- **Alarms cleared:** no\n"
+        );
+        assert_eq!(fields.values.len(), 2);
+    }
+
+    #[test]
+    fn errors_by_field_name() {
+        let errors = template()
+            .compile(
+                &inputs(&[
+                    ("date", "01/05/2024"),
+                    ("current", "-1"),
+                    ("cleared", "true"),
+                ]),
+                Locale::English,
+            )
+            .unwrap_err();
+        assert_eq!(
+            errors.keys().collect::<Vec<_>>(),
+            ["current", "date", "shift"]
+        );
+    }
+
+    #[test]
+    fn field_values() {
+        let template = template();
+        let field = |name: &str| template.fields.iter().find(|f| f.name == name).unwrap();
+
+        let current = field("current");
+        assert_eq!(
+            current.value("2.5", Locale::English),
+            Ok(Some(serde_json::json!(2.5)))
+        );
+        assert_eq!(
+            current.value("500", Locale::English),
+            Ok(Some(serde_json::json!(500)))
+        );
+        assert!(current.value("500.1", Locale::English).is_err());
+        assert!(current.value("twelve", Locale::English).is_err());
+
+        let shift = field("shift");
+        assert!(shift.value("Swing", Locale::English).is_err());
+        assert!(shift.value(" ", Locale::English).is_err());
+        // The messages in the language of the page
+        assert_eq!(
+            current.value("-1", Locale::Japanese),
+            Err("最小値: 0".to_string())
+        );
+
+        assert_eq!(
+            field("cleared").value("true", Locale::English),
+            Ok(Some(true.into()))
+        );
+        assert_eq!(field("notes").value("", Locale::English), Ok(None));
+        assert!(field("date").value("2024-02-30", Locale::English).is_err());
+    }
+}
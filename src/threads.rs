@@ -0,0 +1,127 @@
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::timezone::*;
+use crate::Msg;
+
+impl Model {
+    pub fn find_entry(&self, id: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    // Loaded replies to the entry, in the chronological order
+    pub fn replies(&self, id: &str) -> Vec<&Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.parent.as_deref() == Some(id))
+            .collect()
+    }
+
+    // Buttons acting on an entry
+    pub fn entry_actions(&self, ctx: &Context<Self>, entry: &Entry) -> Html {
+        let id = entry.id.clone();
+        let reply = ctx.link().callback(move |_| Msg::ReplyTo(Some(id.clone())));
+        html! {
+            <span class="entry-actions">
+                <button title={self.t(Text::Reply)} onclick={reply}>
+                    <i class="material-symbols-outlined" aria-hidden="true">{"reply"}</i>
+                </button>
+            </span>
+        }
+    }
+
+    // Link back to the parent, for a reply in the timeline
+    pub fn reply_link(&self, ctx: &Context<Self>, entry: &Entry) -> Html {
+        let Some(parent_id) = entry.parent.clone() else {
+            return html! {};
+        };
+
+        match self.find_entry(&parent_id) {
+            Some(parent) => {
+                let onclick = ctx
+                    .link()
+                    .callback(move |_| Msg::ShowEntry(parent_id.clone()));
+                html! {
+                    <button class="reply-link" {onclick}>
+                        <i class="material-symbols-outlined" aria-hidden="true">{"subdirectory_arrow_right"}</i>
+                        { format!("{} {} {}", self.t(Text::ReplyTo), self.zone.format(&parent.timestamp, "%m-%d %H:%M"), parent.summary(60)) }
+                    </button>
+                }
+            }
+            None => html! {
+                <span class="reply-link">
+                    <i class="material-symbols-outlined" aria-hidden="true">{"subdirectory_arrow_right"}</i>
+                    { self.t(Text::ReplyToEarlier) }
+                </span>
+            },
+        }
+    }
+
+    // Collapsed replies under the original entry
+    pub fn thread_view(&self, ctx: &Context<Self>, entry: &Entry) -> Html {
+        let replies = self.replies(&entry.id);
+        if replies.is_empty() {
+            return html! {};
+        }
+
+        let expanded = self.expanded_threads.contains(&entry.id);
+        let id = entry.id.clone();
+        let toggle = ctx.link().callback(move |_| Msg::ToggleThread(id.clone()));
+        let label = match replies.len() {
+            1 => format!("1 {}", self.t(Text::ReplyCountOne)),
+            n => format!("{} {}", n, self.t(Text::ReplyCount)),
+        };
+
+        html! {
+            <div class={classes!("thread", expanded.then_some("expanded"))}>
+                <button class="thread-toggle" onclick={toggle}>
+                    <i class="material-symbols-outlined" aria-hidden="true">
+                        { if expanded { "expand_less" } else { "expand_more" } }
+                    </i>
+                    { label }
+                </button>
+                if expanded {
+                    <ul class="thread-replies">
+                        {
+                            for replies.into_iter().map(|reply| {
+                                let (log_html, _) = self.markdown_to_html(reply);
+                                let id = reply.id.clone();
+                                let show = ctx.link().callback(move |_| Msg::ShowEntry(id.clone()));
+                                html! {
+                                    <li key={reply.id.clone()} class={classes!("thread-reply", reply.severity.as_str())}>
+                                        <button class="timestamp" title={utc_title(&reply.timestamp)} onclick={show}>
+                                            { self.zone.format(&reply.timestamp, "%m-%d %H:%M") }
+                                        </button>
+                                        <div class="log-text">{ log_html }</div>
+                                    </li>
+                                }
+                            })
+                        }
+                    </ul>
+                }
+            </div>
+        }
+    }
+
+    // The entry being replied to, above the editor
+    pub fn reply_banner(&self, ctx: &Context<Self>) -> Html {
+        let Some(parent_id) = self.draft.reply_to.as_ref() else {
+            return html! {};
+        };
+        let summary = self
+            .find_entry(parent_id)
+            .map(|parent| parent.summary(80))
+            .unwrap_or_default();
+
+        html! {
+            <div class="reply-banner">
+                <i class="material-symbols-outlined" aria-hidden="true">{"reply"}</i>
+                <span>{ format!("{} {}", self.t(Text::ReplyingTo), summary) }</span>
+                <button class="close-button" aria-label={self.t(Text::Close)} onclick={ctx.link().callback(|_| Msg::ReplyTo(None))}>
+                    {"×"}
+                </button>
+            </div>
+        }
+    }
+}
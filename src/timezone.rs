@@ -0,0 +1,181 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+use crate::i18n::*;
+use crate::models::*;
+use crate::Msg;
+
+// Time zone of the experiment site
+pub const SITE_TIMEZONE: Tz = chrono_tz::Asia::Tokyo;
+
+// Zone the times are shown in, also deciding the day grouping
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DisplayZone {
+    // As set in the browser
+    #[default]
+    Browser,
+    Utc,
+    Site,
+}
+
+impl DisplayZone {
+    pub const ALL: [DisplayZone; 3] = [DisplayZone::Browser, DisplayZone::Utc, DisplayZone::Site];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisplayZone::Browser => "browser",
+            DisplayZone::Utc => "utc",
+            DisplayZone::Site => "site",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "utc" => DisplayZone::Utc,
+            "site" => DisplayZone::Site,
+            _ => DisplayZone::Browser,
+        }
+    }
+
+    pub fn label(&self, locale: Locale) -> String {
+        match self {
+            DisplayZone::Browser => format!(
+                "{} ({})",
+                locale.text(Text::ZoneBrowser),
+                Local::now().format("UTC%:z")
+            ),
+            DisplayZone::Utc => "UTC".to_string(),
+            DisplayZone::Site => {
+                format!("{} ({})", locale.text(Text::ZoneSite), SITE_TIMEZONE.name())
+            }
+        }
+    }
+
+    // Calendar day of the time in this zone
+    pub fn date<T: TimeZone>(&self, time: &DateTime<T>) -> NaiveDate {
+        match self {
+            DisplayZone::Browser => time.with_timezone(&Local).date_naive(),
+            DisplayZone::Utc => time.with_timezone(&Utc).date_naive(),
+            DisplayZone::Site => time.with_timezone(&SITE_TIMEZONE).date_naive(),
+        }
+    }
+
+    pub fn format<T: TimeZone>(&self, time: &DateTime<T>, format: &str) -> String {
+        match self {
+            DisplayZone::Browser => time.with_timezone(&Local).format(format).to_string(),
+            DisplayZone::Utc => time.with_timezone(&Utc).format(format).to_string(),
+            DisplayZone::Site => time
+                .with_timezone(&SITE_TIMEZONE)
+                .format(format)
+                .to_string(),
+        }
+    }
+
+    // The wall clock time taken in this zone
+    pub fn local_to_utc(&self, time: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            DisplayZone::Browser => Local
+                .from_local_datetime(time)
+                .earliest()
+                .map(|time| time.with_timezone(&Utc)),
+            DisplayZone::Utc => Some(Utc.from_utc_datetime(time)),
+            DisplayZone::Site => SITE_TIMEZONE
+                .from_local_datetime(time)
+                .earliest()
+                .map(|time| time.with_timezone(&Utc)),
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.date(&Utc::now())
+    }
+}
+
+// Absolute time, for the tooltips
+pub fn utc_title<T: TimeZone>(time: &DateTime<T>) -> String {
+    time.with_timezone(&Utc)
+        .format("%Y-%m-%d %H:%M:%S UTC")
+        .to_string()
+}
+
+// The display zone is remembered over reloads
+const ZONE_KEY: &str = "elogbook.timezone";
+
+pub fn load_zone() -> DisplayZone {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(ZONE_KEY).ok().flatten())
+        .map(|value| DisplayZone::parse(&value))
+        .unwrap_or_default()
+}
+
+pub fn save_zone(zone: DisplayZone) {
+    if let Some(storage) =
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    {
+        let _ = storage.set_item(ZONE_KEY, zone.as_str());
+    }
+}
+
+impl Model {
+    // Submission time of an entry logged after the fact
+    pub fn backdated_badge(&self, entry: &Entry) -> Html {
+        if !entry.backdated() {
+            return html! {};
+        }
+        html! {
+            <time
+                class="backdated-badge"
+                datetime={entry.created_at.to_rfc3339()}
+                title={format!("{} {}", self.t(Text::Submitted), utc_title(&entry.created_at))}
+            >
+                { format!("{} {}", self.t(Text::Logged), self.zone.format(&entry.created_at, "%m-%d %H:%M")) }
+            </time>
+        }
+    }
+
+    // Event time of the draft, none for the time of the submission
+    pub fn draft_event_time(&self) -> Result<Option<DateTime<Utc>>, String> {
+        let typed = self.draft.event_time.trim();
+        if typed.is_empty() {
+            return Ok(None);
+        }
+        let time = NaiveDateTime::parse_from_str(typed, "%Y-%m-%dT%H:%M")
+            .or_else(|_| NaiveDateTime::parse_from_str(typed, "%Y-%m-%dT%H:%M:%S"))
+            .map_err(|_| format!("{}: {}", self.t(Text::NotATime), typed))?;
+        let time = self.zone.local_to_utc(&time).ok_or_else(|| {
+            format!(
+                "{} ({}): {}",
+                self.t(Text::NoSuchTime),
+                self.zone.label(self.locale),
+                typed
+            )
+        })?;
+        if time > Utc::now() {
+            return Err(self.t(Text::EventInFuture).to_string());
+        }
+        Ok(Some(time))
+    }
+
+    // Selector of the display zone in the header
+    pub fn zone_selector(&self, ctx: &Context<Self>) -> Html {
+        let onchange = ctx.link().callback(|e: Event| {
+            Msg::SetDisplayZone(DisplayZone::parse(
+                &e.target_unchecked_into::<HtmlSelectElement>().value(),
+            ))
+        });
+        html! {
+            <select class="zone-select" title={self.t(Text::TimeZone)} aria-label={self.t(Text::TimeZone)} {onchange}>
+                {
+                    for DisplayZone::ALL.into_iter().map(|zone| html! {
+                        <option value={zone.as_str()} selected={zone == self.zone}>
+                            { zone.label(self.locale) }
+                        </option>
+                    })
+                }
+            </select>
+        }
+    }
+}
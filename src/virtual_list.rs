@@ -0,0 +1,247 @@
+use std::ops::Range;
+
+use chrono::NaiveDate;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+
+use crate::models::*;
+
+// Height of an entry not measured yet
+const ESTIMATED_ROW_HEIGHT: f64 = 60.0;
+// Height of a date separator until one is measured
+const ESTIMATED_DATE_HEIGHT: f64 = 42.0;
+// Rows kept materialised above and below the viewport
+const OVERSCAN: f64 = 800.0;
+
+// Scroll position of the timeline
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    pub scroll_top: f64,
+    pub height: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            scroll_top: 0.0,
+            height: 1000.0,
+        }
+    }
+}
+
+// What stays in place while the rows above change their heights
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScrollAnchor {
+    Bottom,
+    // Entry id and its distance from the top of the viewport
+    Entry(String, f64),
+}
+
+// Measured heights of the rendered rows
+#[derive(Debug, Default)]
+pub struct RowHeights {
+    pub entries: std::collections::HashMap<String, f64>,
+    pub date: Option<f64>,
+}
+
+impl RowHeights {
+    fn entry(&self, id: &str) -> f64 {
+        self.entries
+            .get(id)
+            .copied()
+            .unwrap_or(ESTIMATED_ROW_HEIGHT)
+    }
+
+    pub fn date(&self) -> f64 {
+        self.date.unwrap_or(ESTIMATED_DATE_HEIGHT)
+    }
+}
+
+// Whether a measured height differs from the one known, beyond the rounding
+fn height_changed(old: Option<f64>, height: f64) -> bool {
+    match old {
+        Some(old) => (old - height).abs() > 0.5,
+        None => true,
+    }
+}
+
+pub struct Row<'a> {
+    pub entry: &'a Entry,
+    pub date: NaiveDate,
+    // Whether the row starts a new day in the whole list
+    pub show_date: bool,
+    pub top: f64,
+    pub height: f64,
+}
+
+impl Row<'_> {
+    pub fn bottom(&self) -> f64 {
+        self.top + self.height
+    }
+}
+
+// The rows to materialise for the viewport
+pub fn visible_range(rows: &[Row], viewport: &Viewport) -> Range<usize> {
+    let from = viewport.scroll_top - OVERSCAN;
+    let to = viewport.scroll_top + viewport.height + OVERSCAN;
+    let start = rows.partition_point(|row| row.bottom() < from);
+    let end = rows.partition_point(|row| row.top < to).max(start);
+    start..end
+}
+
+impl Model {
+    // Positions of the shown entries in the whole list
+    pub fn layout(&self) -> Vec<Row<'_>> {
+        let mut rows = vec![];
+        let mut top = 0.0;
+        let mut last_date = None;
+
+        for entry in self.entries.iter().filter(|entry| self.is_shown(entry)) {
+            let date = self.zone.date(&entry.timestamp);
+            let show_date = last_date != Some(date);
+            last_date = Some(date);
+            let mut height = self.row_heights.entry(&entry.id);
+            if show_date {
+                height += self.row_heights.date();
+            }
+            rows.push(Row {
+                entry,
+                date,
+                show_date,
+                top,
+                height,
+            });
+            top += height;
+        }
+
+        rows
+    }
+
+    fn content_element(&self) -> Option<HtmlElement> {
+        self.content_ref.cast::<HtmlElement>()
+    }
+
+    // Read the scroll position, and tell whether other rows are to be materialised
+    pub fn update_viewport(&mut self) -> bool {
+        let Some(content) = self.content_element() else {
+            return false;
+        };
+        self.viewport = Viewport {
+            scroll_top: content.scroll_top() as f64,
+            height: content.client_height() as f64,
+        };
+        let rows = self.layout();
+        visible_range(&rows, &self.viewport) != self.rendered_range
+    }
+
+    // Remember what is on the screen before the layout changes
+    pub fn capture_scroll_anchor(&mut self) {
+        let Some(content) = self.content_element() else {
+            return;
+        };
+        if content.scroll_top() + content.client_height() >= content.scroll_height() - 2 {
+            self.scroll_anchor = Some(ScrollAnchor::Bottom);
+            return;
+        }
+
+        let content_top = content.get_bounding_client_rect().top();
+        let document = web_sys::window().unwrap().document().unwrap();
+        let rows = self.layout();
+        self.scroll_anchor = rows
+            .get(self.rendered_range.clone())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| {
+                let element = document.get_element_by_id(&format!("entry-{}", row.entry.id))?;
+                let rect = element.get_bounding_client_rect();
+                (rect.bottom() > content_top)
+                    .then(|| ScrollAnchor::Entry(row.entry.id.clone(), rect.top() - content_top))
+            })
+            .next();
+    }
+
+    // Move the viewport to the anchor in the new layout, so that it gets materialised
+    // The exact position is restored from the DOM once rendered
+    pub fn anchor_viewport(&mut self) {
+        let rows = self.layout();
+        let scroll_top = match &self.scroll_anchor {
+            Some(ScrollAnchor::Bottom) => {
+                rows.last().map(|row| row.bottom() - self.viewport.height)
+            }
+            Some(ScrollAnchor::Entry(id, offset)) => rows
+                .iter()
+                .find(|row| &row.entry.id == id)
+                .map(|row| row.top - offset),
+            None => None,
+        };
+        if let Some(scroll_top) = scroll_top {
+            self.viewport.scroll_top = scroll_top.max(0.0);
+        }
+    }
+
+    // Scroll back to the anchor after the layout changed
+    pub fn restore_scroll_anchor(&mut self) {
+        let (Some(anchor), Some(content)) = (self.scroll_anchor.take(), self.content_element())
+        else {
+            return;
+        };
+        match anchor {
+            ScrollAnchor::Bottom => content.set_scroll_top(content.scroll_height()),
+            ScrollAnchor::Entry(id, offset) => {
+                let document = web_sys::window().unwrap().document().unwrap();
+                if let Some(element) = document.get_element_by_id(&format!("entry-{}", id)) {
+                    let content_top = content.get_bounding_client_rect().top();
+                    let moved = element.get_bounding_client_rect().top() - content_top - offset;
+                    content.set_scroll_top(content.scroll_top() + moved.round() as i32);
+                }
+            }
+        }
+        self.viewport.scroll_top = content.scroll_top() as f64;
+    }
+
+    // Measure the materialised rows; true if any height changed
+    pub fn measure_rows(&mut self) -> bool {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let mut measured = vec![];
+        let mut date_height = None;
+
+        let rows = self.layout();
+        for row in rows.get(self.rendered_range.clone()).unwrap_or_default() {
+            let Some(item) = document
+                .get_element_by_id(&format!("entry-{}", row.entry.id))
+                .and_then(|element| element.dyn_into::<HtmlElement>().ok())
+            else {
+                continue;
+            };
+            let rect = item.get_bounding_client_rect();
+            measured.push((row.entry.id.clone(), rect.height()));
+            if date_height.is_none() {
+                if let Some(date) = document.get_element_by_id(&format!("date-{}", row.entry.id)) {
+                    date_height = Some(rect.top() - date.get_bounding_client_rect().top());
+                }
+            }
+        }
+
+        let mut changed = false;
+        for (id, height) in measured {
+            let old = self.row_heights.entries.insert(id, height);
+            changed |= height_changed(old, height);
+        }
+        if let Some(height) = date_height {
+            changed |= height_changed(self.row_heights.date, height);
+            self.row_heights.date = Some(height);
+        }
+        changed
+    }
+
+    // (Re-)observe the top of the timeline; an intersecting sentinel is reported at once
+    pub fn observe_sentinel(&self) {
+        if let (Some(observer), Some(sentinel)) = (
+            self.sentinel_observer.as_ref(),
+            self.sentinel_ref.cast::<web_sys::Element>(),
+        ) {
+            observer.unobserve(&sentinel);
+            observer.observe(&sentinel);
+        }
+    }
+}